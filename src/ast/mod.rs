@@ -1,18 +1,24 @@
 mod tests;
 
 use std::any::Any;
+use std::rc::Rc;
 
 use dyn_clone::DynClone;
 
-use crate::token::Token;
+use crate::lexer::Lexer;
+use crate::token::{Span, Token};
 
 /// Represents a node in the AST. Each node implements the `token_literal` function, which
 /// is mainly used for debugging purposes. It returns the literal of the token associated
 /// with this node.
+///
+/// Each node also reports the span of source text it was parsed from, which runs from the
+/// first token of the node to its last.
 pub trait Node: DynClone {
     fn token_literal(&self) -> String;
     fn as_any(&self) -> &dyn Any;
     fn string(&self) -> String;
+    fn span(&self) -> Span;
 }
 
 pub trait Statement: Node {}
@@ -43,6 +49,13 @@ impl Node for Program {
         self
     }
 
+    fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default(),
+        }
+    }
+
     fn string(&self) -> String {
         let mut ret = Vec::new();
         for statements in &self.statements {
@@ -57,12 +70,14 @@ impl Node for Program {
 #[derive(Clone)]
 pub struct Identifier {
     pub token: Token,
+    pub span: Span,
     pub value: String,
 }
 
 impl Identifier {
     pub fn new(token: Token, value: &str) -> Self {
         Identifier {
+            span: token.span,
             token,
             value: value.to_string(),
         }
@@ -78,6 +93,10 @@ impl Node for Identifier {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         self.value.clone()
     }
@@ -92,20 +111,38 @@ impl Expression for Identifier {}
 #[derive(Clone)]
 pub struct LetStatement {
     pub token: Token,
+    pub span: Span,
     pub name: Identifier,
     pub value: Box<dyn Expression>,
     pub rec: bool,
+    // Whether the statement is a `var` statement, whose binding can be assigned to
+    pub mutable: bool,
+    // The bindings after the first one in `let rec f = ... and g = ...;`, which are defined
+    // together with the first binding so they can refer to each other
+    pub and_bindings: Vec<(Identifier, Box<dyn Expression>)>,
 }
 
 impl LetStatement {
     pub fn new(token: Token, name: Identifier, value: Box<dyn Expression>, rec: bool) -> Self {
         LetStatement {
+            span: token.span.to(value.span()),
             token,
             name,
             value,
             rec,
+            mutable: false,
+            and_bindings: Vec::new(),
         }
     }
+
+    /// Returns every binding of the statement in source order.
+    pub fn bindings(&self) -> impl Iterator<Item = (&Identifier, &dyn Expression)> {
+        std::iter::once((&self.name, self.value.as_ref())).chain(
+            self.and_bindings
+                .iter()
+                .map(|(name, value)| (name, value.as_ref())),
+        )
+    }
 }
 
 impl Node for LetStatement {
@@ -117,11 +154,22 @@ impl Node for LetStatement {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
+        let bindings = self
+            .bindings()
+            .map(|(name, value)| format!("{} = {}", name.string(), value.string()))
+            .collect::<Vec<_>>()
+            .join(" and ");
         if self.rec {
-            format!("let rec {} = {};", self.name.string(), self.value.string())
+            format!("let rec {bindings};")
+        } else if self.mutable {
+            format!("var {bindings};")
         } else {
-            format!("let {} = {};", self.name.string(), self.value.string())
+            format!("let {bindings};")
         }
     }
 }
@@ -135,12 +183,14 @@ impl Statement for LetStatement {}
 #[derive(Clone)]
 pub struct ReturnStatement {
     pub token: Token,
+    pub span: Span,
     pub return_value: Box<dyn Expression>,
 }
 
 impl ReturnStatement {
     pub fn new(token: Token, return_value: Box<dyn Expression>) -> Self {
         Self {
+            span: token.span.to(return_value.span()),
             token,
             return_value,
         }
@@ -156,6 +206,10 @@ impl Node for ReturnStatement {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         format!("return {};", self.return_value.string())
     }
@@ -165,17 +219,200 @@ impl Statement for ReturnStatement {}
 
 // ========== Return statement End ==========
 
+// ========== While statement Start ==========
+
+#[derive(Clone)]
+pub struct WhileStatement {
+    pub token: Token,
+    pub span: Span,
+    pub condition: Box<dyn Expression>,
+    pub body: BlockStatement,
+}
+
+impl WhileStatement {
+    pub fn new(token: Token, condition: Box<dyn Expression>, body: BlockStatement) -> Self {
+        Self {
+            span: token.span.to(body.span),
+            token,
+            condition,
+            body,
+        }
+    }
+}
+
+impl Node for WhileStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        format!("while ({}) {}", self.condition.string(), self.body.string())
+    }
+}
+
+impl Statement for WhileStatement {}
+
+// ========== While statement End ==========
+
+// ========== For statement Start ==========
+
+// A `for variable in collection { ... }` loop. The body runs once for each item of the collection,
+// in a new scope where `variable` is bound to the item.
+#[derive(Clone)]
+pub struct ForStatement {
+    pub token: Token,
+    pub span: Span,
+    pub variable: Identifier,
+    pub collection: Box<dyn Expression>,
+    pub body: BlockStatement,
+}
+
+impl ForStatement {
+    pub fn new(
+        token: Token,
+        variable: Identifier,
+        collection: Box<dyn Expression>,
+        body: BlockStatement,
+    ) -> Self {
+        Self {
+            span: token.span.to(body.span),
+            token,
+            variable,
+            collection,
+            body,
+        }
+    }
+}
+
+impl Node for ForStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        format!(
+            "for {} in {} {}",
+            self.variable.string(),
+            self.collection.string(),
+            self.body.string()
+        )
+    }
+}
+
+impl Statement for ForStatement {}
+
+// ========== For statement End ==========
+
+// ========== Break statement Start ==========
+
+#[derive(Clone)]
+pub struct BreakStatement {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl BreakStatement {
+    pub fn new(token: Token) -> Self {
+        Self {
+            span: token.span,
+            token,
+        }
+    }
+}
+
+impl Node for BreakStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        "break;".to_string()
+    }
+}
+
+impl Statement for BreakStatement {}
+
+// ========== Break statement End ==========
+
+// ========== Continue statement Start ==========
+
+#[derive(Clone)]
+pub struct ContinueStatement {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl ContinueStatement {
+    pub fn new(token: Token) -> Self {
+        Self {
+            span: token.span,
+            token,
+        }
+    }
+}
+
+impl Node for ContinueStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        "continue;".to_string()
+    }
+}
+
+impl Statement for ContinueStatement {}
+
+// ========== Continue statement End ==========
+
 // ========== Expression statement Start ==========
 
 #[derive(Clone)]
 pub struct ExpressionStatement {
     pub token: Token,
+    pub span: Span,
     pub expression: Box<dyn Expression>,
 }
 
 impl ExpressionStatement {
     pub fn new(token: Token, expression: Box<dyn Expression>) -> Self {
-        Self { token, expression }
+        Self {
+            span: expression.span(),
+            token,
+            expression,
+        }
     }
 }
 
@@ -188,6 +425,10 @@ impl Node for ExpressionStatement {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         format!("{};", self.expression.string())
     }
@@ -202,12 +443,17 @@ impl Statement for ExpressionStatement {}
 #[derive(Clone)]
 pub struct IntegerLiteral {
     pub token: Token,
+    pub span: Span,
     pub value: i64,
 }
 
 impl IntegerLiteral {
     pub fn new(token: Token, value: i64) -> Self {
-        Self { token, value }
+        Self {
+            span: token.span,
+            token,
+            value,
+        }
     }
 }
 
@@ -220,6 +466,10 @@ impl Node for IntegerLiteral {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         self.value.to_string()
     }
@@ -229,17 +479,63 @@ impl Expression for IntegerLiteral {}
 
 // ========== Integer literal End ==========
 
+// ========== Float literal Start ==========
+
+#[derive(Clone)]
+pub struct FloatLiteral {
+    pub token: Token,
+    pub span: Span,
+    pub value: f64,
+}
+
+impl FloatLiteral {
+    pub fn new(token: Token, value: f64) -> Self {
+        Self {
+            span: token.span,
+            token,
+            value,
+        }
+    }
+}
+
+impl Node for FloatLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        self.token.literal.clone()
+    }
+}
+
+impl Expression for FloatLiteral {}
+
+// ========== Float literal End ==========
+
 // ========== Boolean literal Start ==========
 
 #[derive(Clone)]
 pub struct BooleanLiteral {
     pub token: Token,
+    pub span: Span,
     pub value: bool,
 }
 
 impl BooleanLiteral {
     pub fn new(token: Token, value: bool) -> Self {
-        Self { token, value }
+        Self {
+            span: token.span,
+            token,
+            value,
+        }
     }
 }
 
@@ -252,6 +548,10 @@ impl Node for BooleanLiteral {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         self.value.to_string()
     }
@@ -266,11 +566,15 @@ impl Expression for BooleanLiteral {}
 #[derive(Clone)]
 pub struct NullLiteral {
     pub token: Token,
+    pub span: Span,
 }
 
 impl NullLiteral {
     pub fn new(token: Token) -> Self {
-        Self { token }
+        Self {
+            span: token.span,
+            token,
+        }
     }
 }
 
@@ -283,6 +587,10 @@ impl Node for NullLiteral {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         "null".to_string()
     }
@@ -297,6 +605,7 @@ impl Expression for NullLiteral {}
 #[derive(Clone)]
 pub struct PrefixExpression {
     pub token: Token,
+    pub span: Span,
     pub operator: String,
     pub right: Box<dyn Expression>,
 }
@@ -304,6 +613,7 @@ pub struct PrefixExpression {
 impl PrefixExpression {
     pub fn new(token: Token, operator: &str, right: Box<dyn Expression>) -> Self {
         Self {
+            span: token.span.to(right.span()),
             token,
             operator: operator.to_string(),
             right,
@@ -320,6 +630,10 @@ impl Node for PrefixExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         format!("({}{})", self.operator, self.right.string())
     }
@@ -334,6 +648,7 @@ impl Expression for PrefixExpression {}
 #[derive(Clone)]
 pub struct InfixExpression {
     pub token: Token,
+    pub span: Span,
     pub operator: String,
     pub left: Box<dyn Expression>,
     pub right: Box<dyn Expression>,
@@ -347,6 +662,7 @@ impl InfixExpression {
         right: Box<dyn Expression>,
     ) -> Self {
         Self {
+            span: left.span().to(right.span()),
             token,
             operator: operator.to_string(),
             left,
@@ -364,6 +680,10 @@ impl Node for InfixExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         format!(
             "({} {} {})",
@@ -378,11 +698,70 @@ impl Expression for InfixExpression {}
 
 // ========== Infix expression End ==========
 
+// ========== Assign expression Start ==========
+
+// An assignment like `x = 1` or `arr[i] += 2`. The parser makes sure `target` is an identifier, or
+// an index expression whose collection is itself a valid target. `operator` is `=` or one of the
+// compound assignment operators.
+#[derive(Clone)]
+pub struct AssignExpression {
+    pub token: Token,
+    pub span: Span,
+    pub target: Box<dyn Expression>,
+    pub operator: String,
+    pub value: Box<dyn Expression>,
+}
+
+impl AssignExpression {
+    pub fn new(
+        token: Token,
+        target: Box<dyn Expression>,
+        operator: &str,
+        value: Box<dyn Expression>,
+    ) -> Self {
+        Self {
+            span: target.span().to(value.span()),
+            token,
+            target,
+            operator: operator.to_string(),
+            value,
+        }
+    }
+}
+
+impl Node for AssignExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        format!(
+            "({} {} {})",
+            self.target.string(),
+            self.operator,
+            self.value.string()
+        )
+    }
+}
+
+impl Expression for AssignExpression {}
+
+// ========== Assign expression End ==========
+
 // ========== IfExpression Start ==========
 
 #[derive(Clone)]
 pub struct IfExpression {
     pub token: Token,
+    pub span: Span,
     pub consequences: Vec<(Box<dyn Expression>, BlockStatement)>,
     pub alternative: Option<BlockStatement>,
 }
@@ -397,7 +776,12 @@ impl IfExpression {
             !consequences.is_empty(),
             "IfExpression must have at least one consequence"
         );
+        let last_block = match alternative.as_ref() {
+            Some(alternative) => alternative,
+            None => &consequences[consequences.len() - 1].1,
+        };
         Self {
+            span: token.span.to(last_block.span),
             token,
             consequences,
             alternative,
@@ -414,6 +798,10 @@ impl Node for IfExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let mut ret = format!(
             "if ({}) {}",
@@ -443,12 +831,17 @@ impl Expression for IfExpression {}
 #[derive(Clone)]
 pub struct BlockStatement {
     pub token: Token,
+    pub span: Span,
     pub statements: Vec<Box<dyn Statement>>,
 }
 
 impl BlockStatement {
     pub fn new(token: Token, statements: Vec<Box<dyn Statement>>) -> Self {
-        Self { token, statements }
+        Self {
+            span: token.span,
+            token,
+            statements,
+        }
     }
 }
 
@@ -461,6 +854,10 @@ impl Node for BlockStatement {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let statements = self
             .statements
@@ -481,16 +878,19 @@ impl Statement for BlockStatement {}
 #[derive(Clone)]
 pub struct FunctionLiteral {
     pub token: Token,
+    pub span: Span,
     pub parameters: Vec<Identifier>,
-    pub body: BlockStatement,
+    // Shared with the functions created from the literal, so that creating one doesn't copy it
+    pub body: Rc<BlockStatement>,
 }
 
 impl FunctionLiteral {
     pub fn new(token: Token, parameters: Vec<Identifier>, body: BlockStatement) -> Self {
         Self {
+            span: token.span.to(body.span),
             token,
             parameters,
-            body,
+            body: Rc::new(body),
         }
     }
 }
@@ -504,6 +904,10 @@ impl Node for FunctionLiteral {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let parameter_string = self
             .parameters
@@ -523,8 +927,9 @@ impl Expression for FunctionLiteral {}
 
 #[derive(Clone)]
 pub struct CallExpression {
-    pub token: Token,                  // The ( token
-    pub function: Box<dyn Expression>, // Even though the type allows for any Expression here, in practice this should only be an identifier or a function literal
+    pub token: Token, // The ( token
+    pub span: Span,
+    pub function: Box<dyn Expression>, // Any expression that evaluates to a function
     pub arguments: Vec<Box<dyn Expression>>,
 }
 
@@ -535,6 +940,7 @@ impl CallExpression {
         arguments: Vec<Box<dyn Expression>>,
     ) -> Self {
         Self {
+            span: function.span().to(token.span),
             token,
             function,
             arguments,
@@ -551,6 +957,10 @@ impl Node for CallExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let function = self.function.string();
         let arguments = self
@@ -572,17 +982,19 @@ impl Expression for CallExpression {}
 #[derive(Clone)]
 pub struct IndexExpression {
     pub token: Token,
-    // Even though the type allows any Expression here, this should only be an array expression or
-    // a map expression (if I ever add map expressions)
+    pub span: Span,
+    // Even though the type allows any Expression here, this should evaluate to an array, a string
+    // or a hash
     pub collection: Box<dyn Expression>,
-    // Even though the type allows any Expression here, this should only be an integer (for array
-    // indexing) or a string (for map indexing)
+    // Even though the type allows any Expression here, this should evaluate to an integer (for
+    // array and string indexing) or a hash key (for hash indexing)
     pub index: Box<dyn Expression>,
 }
 
 impl IndexExpression {
     pub fn new(token: Token, collection: Box<dyn Expression>, index: Box<dyn Expression>) -> Self {
         Self {
+            span: collection.span().to(index.span()),
             token,
             collection,
             index,
@@ -599,6 +1011,10 @@ impl Node for IndexExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let array_string = self.collection.string();
         let index_string = self.index.string();
@@ -610,17 +1026,85 @@ impl Expression for IndexExpression {}
 
 // ========== Index expression End ==========
 
+// ========== Slice expression Start ==========
+
+/// A slice of an array or string, like `items[1:3]`. Either bound can be left out, in which case
+/// the slice starts at the beginning or goes to the end.
+#[derive(Clone)]
+pub struct SliceExpression {
+    pub token: Token,
+    pub span: Span,
+    pub collection: Box<dyn Expression>,
+    pub start: Option<Box<dyn Expression>>,
+    pub end: Option<Box<dyn Expression>>,
+}
+
+impl SliceExpression {
+    pub fn new(
+        token: Token,
+        collection: Box<dyn Expression>,
+        start: Option<Box<dyn Expression>>,
+        end: Option<Box<dyn Expression>>,
+    ) -> Self {
+        Self {
+            span: collection.span().to(token.span),
+            token,
+            collection,
+            start,
+            end,
+        }
+    }
+}
+
+impl Node for SliceExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        let bound_string = |bound: &Option<Box<dyn Expression>>| {
+            bound
+                .as_ref()
+                .map(|bound| bound.string())
+                .unwrap_or_default()
+        };
+        format!(
+            "{}[{}:{}]",
+            self.collection.string(),
+            bound_string(&self.start),
+            bound_string(&self.end)
+        )
+    }
+}
+
+impl Expression for SliceExpression {}
+
+// ========== Slice expression End ==========
+
 // ========== Array expression Start ==========
 
 #[derive(Clone)]
 pub struct ArrayExpression {
     pub token: Token,
+    pub span: Span,
     pub items: Vec<Box<dyn Expression>>,
 }
 
 impl ArrayExpression {
     pub fn new(token: Token, items: Vec<Box<dyn Expression>>) -> Self {
-        Self { token, items }
+        Self {
+            span: token.span,
+            token,
+            items,
+        }
     }
 }
 
@@ -633,6 +1117,10 @@ impl Node for ArrayExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
         let items = self
             .items
@@ -648,17 +1136,70 @@ impl Expression for ArrayExpression {}
 
 // ========== Array expression End ==========
 
+// ========== Hash expression Start ==========
+
+#[derive(Clone)]
+pub struct HashExpression {
+    pub token: Token, // The { token
+    pub span: Span,
+    // The key and value expressions in source order
+    pub pairs: Vec<(Box<dyn Expression>, Box<dyn Expression>)>,
+}
+
+impl HashExpression {
+    pub fn new(token: Token, pairs: Vec<(Box<dyn Expression>, Box<dyn Expression>)>) -> Self {
+        Self {
+            span: token.span,
+            token,
+            pairs,
+        }
+    }
+}
+
+impl Node for HashExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.string(), value.string()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("{{{pairs}}}")
+    }
+}
+
+impl Expression for HashExpression {}
+
+// ========== Hash expression End ==========
+
 // ========== String expression Start ==========
 
 #[derive(Clone)]
 pub struct StringExpression {
     pub token: Token,
+    pub span: Span,
     pub value: String,
 }
 
 impl StringExpression {
     pub fn new(token: Token, value: String) -> Self {
-        Self { token, value }
+        Self {
+            span: token.span,
+            token,
+            value,
+        }
     }
 }
 
@@ -671,11 +1212,70 @@ impl Node for StringExpression {
         self
     }
 
+    fn span(&self) -> Span {
+        self.span
+    }
+
     fn string(&self) -> String {
-        format!("\"{}\"", self.value)
+        Lexer::escape_string(&self.value)
     }
 }
 
 impl Expression for StringExpression {}
 
 // ========== String expression End ==========
+
+// ========== Interpolated string expression Start ==========
+
+/// A string literal with `${...}` interpolations, like `"hello ${name}!"`. The text of the
+/// literal is split around the interpolations, so `strings` always has one more element than
+/// `expressions`, and the value is `strings[0] + expressions[0] + strings[1] + ...`.
+#[derive(Clone)]
+pub struct InterpolatedStringExpression {
+    pub token: Token,
+    pub span: Span,
+    pub strings: Vec<String>,
+    pub expressions: Vec<Box<dyn Expression>>,
+}
+
+impl InterpolatedStringExpression {
+    pub fn new(token: Token, strings: Vec<String>, expressions: Vec<Box<dyn Expression>>) -> Self {
+        Self {
+            span: token.span,
+            token,
+            strings,
+            expressions,
+        }
+    }
+}
+
+impl Node for InterpolatedStringExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn string(&self) -> String {
+        let mut ret = String::from('"');
+        for (string, expression) in self.strings.iter().zip(&self.expressions) {
+            ret.push_str(&Lexer::escape_string_text(string));
+            ret.push_str(&format!("${{{}}}", expression.string()));
+        }
+        if let Some(last) = self.strings.last() {
+            ret.push_str(&Lexer::escape_string_text(last));
+        }
+        ret.push('"');
+        ret
+    }
+}
+
+impl Expression for InterpolatedStringExpression {}
+
+// ========== Interpolated string expression End ==========
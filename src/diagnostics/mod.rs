@@ -0,0 +1,244 @@
+mod tests;
+
+use crate::evaluator::EvaluatorError;
+use crate::parser::ParserError;
+use crate::token::Span;
+
+// Consecutive traceback entries for the same line are collapsed after this many repetitions, so
+// deep recursion doesn't bury the error under thousands of identical lines
+const MAX_REPEATED_TRACEBACK_ENTRIES: usize = 3;
+
+// ANSI escape codes used when rendering diagnostics for a terminal
+const RED_BOLD: &str = "\x1b[1;31m";
+const BLUE_BOLD: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// An error report that can be rendered against the source text it refers to.
+///
+/// A rendered diagnostic looks like this:
+///
+/// ```text
+/// error[R0001]: Unknown identifier found: y
+///  --> script.donk:2:9
+///   |
+/// 2 | let x = y + 1;
+///   |         ^
+///   |
+///   = help: ...
+/// ```
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub code: Option<&'static str>,
+    pub span: Option<Span>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            code: None,
+            span: None,
+            help: None,
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help = Some(help.to_string());
+        self
+    }
+
+    /// Renders the diagnostic without any escape codes, which is suitable for log files.
+    pub fn render_plain(&self, filename: &str, source: &str) -> String {
+        self.render(filename, source, false)
+    }
+
+    /// Renders the diagnostic with ANSI colors, which is suitable for a terminal.
+    pub fn render_colored(&self, filename: &str, source: &str) -> String {
+        self.render(filename, source, true)
+    }
+
+    fn render(&self, filename: &str, source: &str, colored: bool) -> String {
+        let paint = |style: &str, text: &str| {
+            if colored {
+                format!("{style}{text}{RESET}")
+            } else {
+                text.to_string()
+            }
+        };
+
+        let severity = match self.code {
+            Some(code) => format!("error[{code}]"),
+            None => "error".to_string(),
+        };
+        let mut ret = format!(
+            "{}{}\n",
+            paint(RED_BOLD, &severity),
+            paint(BOLD, &format!(": {}", self.message))
+        );
+
+        // Nodes built outside the parser have a default span with a line number of 0, which
+        // doesn't point anywhere in the source
+        let Some(span) = self.span.filter(|span| span.start.line > 0) else {
+            ret.push_str(&format!(" {} {filename}\n", paint(BLUE_BOLD, "-->")));
+            if let Some(help) = self.help.as_ref() {
+                ret.push_str(&format!(" {} {help}\n", paint(BLUE_BOLD, "= help:")));
+            }
+            return ret;
+        };
+
+        let line_number = span.start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line = source.lines().nth(span.start.line - 1).unwrap_or("");
+        ret.push_str(&format!(
+            "{gutter}{} {filename}:{}:{}\n",
+            paint(BLUE_BOLD, "-->"),
+            span.start.line,
+            span.start.column
+        ));
+        ret.push_str(&format!("{gutter} {}\n", paint(BLUE_BOLD, "|")));
+        ret.push_str(&format!(
+            "{} {line}\n",
+            paint(BLUE_BOLD, &format!("{line_number} |"))
+        ));
+        ret.push_str(&format!(
+            "{gutter} {} {}{}\n",
+            paint(BLUE_BOLD, "|"),
+            Self::caret_padding(line, span.start.column),
+            paint(RED_BOLD, &"^".repeat(Self::caret_width(line, span)))
+        ));
+        if let Some(help) = self.help.as_ref() {
+            ret.push_str(&format!("{gutter} {}\n", paint(BLUE_BOLD, "|")));
+            ret.push_str(&format!(
+                "{gutter} {} {help}\n",
+                paint(BLUE_BOLD, "= help:")
+            ));
+        }
+        ret
+    }
+
+    // Whitespace that lines the carets up under the start of the span. Tabs in the source line
+    // are kept as tabs so the carets line up no matter how wide the terminal renders them.
+    fn caret_padding(line: &str, column: usize) -> String {
+        line.chars()
+            .take(column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect()
+    }
+
+    // The number of carets to draw. Spans covering multiple lines are underlined to the end of
+    // the first line, and empty spans (like the end of the file) still get a single caret.
+    fn caret_width(line: &str, span: Span) -> usize {
+        let end_column = if span.end.line == span.start.line {
+            span.end.column
+        } else {
+            line.chars().count() + 1
+        };
+        end_column.saturating_sub(span.start.column).max(1)
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(error: &ParserError) -> Self {
+        let diagnostic = Diagnostic::error(&error.kind.to_string())
+            .with_code(error.code())
+            .with_span(error.span);
+        match error.help() {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&EvaluatorError> for Diagnostic {
+    fn from(error: &EvaluatorError) -> Self {
+        let mut diagnostic = Diagnostic::error(&error.kind.to_string()).with_code(error.code());
+        if let Some(span) = error.span {
+            diagnostic = diagnostic.with_span(span);
+        }
+        match error.help() {
+            Some(help) => diagnostic.with_help(help),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Renders the call stack of a runtime error like a Python traceback, with the most recent call
+/// last. Each entry shows the line that was executing in a function when the error happened.
+/// Returns an empty string for errors that happened outside of any function.
+///
+/// ```text
+/// Traceback (most recent call last):
+///   File "script.donk", line 9, in <main>
+///     println(factorial(5));
+///   File "script.donk", line 5, in factorial
+///     return n * factorial(n - 1);
+/// ```
+pub fn render_traceback(error: &EvaluatorError, filename: &str, source: &str) -> String {
+    if error.backtrace.is_empty() {
+        return String::new();
+    }
+    // The line executing in each function is the call site of the next frame, or the location of
+    // the error for the innermost frame
+    let functions = std::iter::once("<main>").chain(
+        error
+            .backtrace
+            .iter()
+            .map(|frame| frame.function.as_deref().unwrap_or("<anonymous>")),
+    );
+    let locations = error
+        .backtrace
+        .iter()
+        .map(|frame| Some(frame.call_site))
+        .chain(std::iter::once(error.span));
+
+    let mut ret = "Traceback (most recent call last):\n".to_string();
+    let mut previous_entry = None;
+    let mut repeats = 0;
+    for (function, location) in functions.zip(locations) {
+        let line = location
+            .map(|span| span.start.line)
+            .filter(|line| *line > 0);
+        if previous_entry == Some((function, line)) {
+            repeats += 1;
+            if repeats >= MAX_REPEATED_TRACEBACK_ENTRIES {
+                continue;
+            }
+        } else {
+            push_repeat_note(&mut ret, repeats);
+            previous_entry = Some((function, line));
+            repeats = 0;
+        }
+        match line {
+            Some(line) => {
+                ret.push_str(&format!(
+                    "  File \"{filename}\", line {line}, in {function}\n"
+                ));
+                let code = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+                ret.push_str(&format!("    {}\n", code.trim()));
+            }
+            None => ret.push_str(&format!("  File \"{filename}\", in {function}\n")),
+        }
+    }
+    push_repeat_note(&mut ret, repeats);
+    ret
+}
+
+fn push_repeat_note(traceback: &mut String, repeats: usize) {
+    if repeats >= MAX_REPEATED_TRACEBACK_ENTRIES {
+        let hidden = repeats + 1 - MAX_REPEATED_TRACEBACK_ENTRIES;
+        traceback.push_str(&format!("  [Previous line repeated {hidden} more times]\n"));
+    }
+}
@@ -0,0 +1,138 @@
+#[cfg(test)]
+use crate::diagnostics::{Diagnostic, render_traceback};
+#[cfg(test)]
+use crate::evaluator::Evaluator;
+#[cfg(test)]
+use crate::evaluator::environment::Environment;
+#[cfg(test)]
+use crate::lexer::Lexer;
+#[cfg(test)]
+use crate::parser::Parser;
+#[cfg(test)]
+use crate::token::{Position, Span};
+
+#[test]
+fn render_plain_with_span() {
+    let source = "let x = 5;\nlet y = foo + x;\n";
+    let diagnostic = Diagnostic::error("Unknown identifier found: foo")
+        .with_span(Span::new(Position::new(2, 9, 19), Position::new(2, 12, 22)))
+        .with_help("did you mean `x`?");
+    let expected = "\
+error: Unknown identifier found: foo
+ --> test.donk:2:9
+  |
+2 | let y = foo + x;
+  |         ^^^
+  |
+  = help: did you mean `x`?
+";
+    assert_eq!(diagnostic.render_plain("test.donk", source), expected);
+}
+
+#[test]
+fn render_plain_without_span() {
+    let diagnostic = Diagnostic::error("Something went wrong");
+    assert_eq!(
+        diagnostic.render_plain("test.donk", ""),
+        "error: Something went wrong\n --> test.donk\n"
+    );
+}
+
+#[test]
+fn render_plain_keeps_tabs_and_clamps_multiline_spans() {
+    let source = "\tif (x) {\n\t\t1\n\t}";
+    let diagnostic = Diagnostic::error("bad if")
+        .with_span(Span::new(Position::new(1, 2, 1), Position::new(3, 3, 17)));
+    let expected = "\
+error: bad if
+ --> test.donk:1:2
+  |
+1 | \tif (x) {
+  | \t^^^^^^^^
+";
+    assert_eq!(diagnostic.render_plain("test.donk", source), expected);
+}
+
+#[test]
+fn render_colored_uses_escape_codes() {
+    let diagnostic = Diagnostic::error("oops")
+        .with_span(Span::new(Position::new(1, 1, 0), Position::new(1, 2, 1)));
+    let rendered = diagnostic.render_colored("test.donk", "x");
+    assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+    assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+}
+
+#[test]
+fn parser_error_diagnostic() {
+    let source = "let x = (1 + 2;";
+    let mut parser = Parser::new(Lexer::new(source));
+    parser.parse_program();
+    let diagnostic = Diagnostic::from(&parser.errors()[0]);
+    let rendered = diagnostic.render_plain("test.donk", source);
+    assert!(
+        rendered.starts_with("error[P0001]: Expected RParen, found Semicolon instead\n"),
+        "{rendered}"
+    );
+    assert!(rendered.contains(" --> test.donk:1:15\n"), "{rendered}");
+    assert!(rendered.contains("  |               ^\n"), "{rendered}");
+}
+
+#[test]
+fn runtime_error_diagnostic() {
+    let source = "let x = 1;\nlet y = x + [1, 2];";
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    let Err(error) = Evaluator::new().eval(&program, &mut Environment::new()) else {
+        panic!("Expected a runtime error");
+    };
+    let rendered = Diagnostic::from(&error).render_plain("test.donk", source);
+    assert!(
+        rendered.starts_with("error[R0002]: Expected integer, found array instead\n"),
+        "{rendered}"
+    );
+    assert!(rendered.contains(" --> test.donk:2:13\n"), "{rendered}");
+    assert!(rendered.contains("  |             ^^^^^^\n"), "{rendered}");
+}
+
+#[test]
+fn runtime_error_traceback() {
+    let source = "\
+let fail = fun(x) {
+    x + true
+};
+let rec count = fun(n) {
+    if (n == 0) { fail(n) } else { 1 + count(n - 1) }
+};
+count(6);";
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    let Err(error) = Evaluator::new().eval(&program, &mut Environment::new()) else {
+        panic!("Expected a runtime error");
+    };
+    let expected = "\
+Traceback (most recent call last):
+  File \"test.donk\", line 7, in <main>
+    count(6);
+  File \"test.donk\", line 5, in count
+    if (n == 0) { fail(n) } else { 1 + count(n - 1) }
+  File \"test.donk\", line 5, in count
+    if (n == 0) { fail(n) } else { 1 + count(n - 1) }
+  File \"test.donk\", line 5, in count
+    if (n == 0) { fail(n) } else { 1 + count(n - 1) }
+  [Previous line repeated 3 more times]
+  File \"test.donk\", line 2, in fail
+    x + true
+";
+    assert_eq!(render_traceback(&error, "test.donk", source), expected);
+}
+
+#[test]
+fn no_traceback_outside_functions() {
+    let source = "1 + true;";
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    let Err(error) = Evaluator::new().eval(&program, &mut Environment::new()) else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(render_traceback(&error, "test.donk", source), "");
+}
@@ -0,0 +1,362 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::evaluator::{EvaluatorError, EvaluatorErrorKind, hash_key};
+use crate::object::memory::{self, string_array_size};
+use crate::object::{
+    Array, BigInteger, BuiltinFn, Float, FromObject, Hash, HashKey, Integer, IntoObject, Object,
+    Range, StringObject,
+};
+
+/// The number of arguments a builtin function accepts. The evaluator checks the arity before
+/// calling a builtin, so builtins don't need to check the number of arguments themselves.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arity {
+    /// Exactly this many arguments.
+    Exact(usize),
+    /// This many arguments or more, for variadic functions.
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(expected) => count == expected,
+            Arity::AtLeast(min) => count >= min,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arity::Exact(expected) => write!(f, "{expected}"),
+            Arity::AtLeast(min) => write!(f, "at least {min}"),
+        }
+    }
+}
+
+/// The arguments a builtin function was called with. Besides giving access to the raw objects, it
+/// has helpers that convert arguments to Rust values and report a type mismatch if an argument
+/// has the wrong type.
+pub struct Arguments {
+    function: String,
+    values: Vec<Box<dyn Object>>,
+}
+
+impl Arguments {
+    pub fn new(function: &str, values: Vec<Box<dyn Object>>) -> Self {
+        Self {
+            function: function.to_string(),
+            values,
+        }
+    }
+
+    /// The name of the builtin function being called.
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Object> {
+        self.values.iter().map(|value| value.as_ref())
+    }
+
+    pub fn into_values(self) -> Vec<Box<dyn Object>> {
+        self.values
+    }
+
+    /// Returns the argument at `index`, or an error if there are not enough arguments.
+    pub fn get(&self, index: usize) -> Result<&dyn Object, EvaluatorError> {
+        self.values
+            .get(index)
+            .map(|value| value.as_ref())
+            .ok_or_else(|| self.invalid(&format!("missing argument {}", index + 1)))
+    }
+
+    /// Converts the argument at `index` to any type that implements `FromObject`.
+    pub fn get_as<T: FromObject>(&self, index: usize) -> Result<T, EvaluatorError> {
+        T::from_object(self.get(index)?).map_err(|error| {
+            if error.path.is_empty() {
+                error.into()
+            } else {
+                // The type mismatch is somewhere inside the argument, so include where
+                self.invalid(&error.to_string())
+            }
+        })
+    }
+
+    pub fn integer(&self, index: usize) -> Result<i64, EvaluatorError> {
+        self.get_as(index)
+    }
+
+    pub fn boolean(&self, index: usize) -> Result<bool, EvaluatorError> {
+        self.get_as(index)
+    }
+
+    /// Returns the argument at `index` as a float. Integers are converted to floats.
+    pub fn number(&self, index: usize) -> Result<f64, EvaluatorError> {
+        let value = self.get(index)?;
+        if is_integer(value) || value.as_any().is::<Float>() {
+            self.get_as(index)
+        } else {
+            Err(type_error("number", value))
+        }
+    }
+
+    pub fn string(&self, index: usize) -> Result<&str, EvaluatorError> {
+        let value = self.get(index)?;
+        match value.as_any().downcast_ref::<StringObject>() {
+            Some(string) => Ok(&string.value),
+            None => Err(type_error("string", value)),
+        }
+    }
+
+    pub fn array(&self, index: usize) -> Result<&[Box<dyn Object>], EvaluatorError> {
+        let value = self.get(index)?;
+        match value.as_any().downcast_ref::<Array>() {
+            Some(array) => Ok(&array.items),
+            None => Err(type_error("array", value)),
+        }
+    }
+
+    pub fn hash(
+        &self,
+        index: usize,
+    ) -> Result<&BTreeMap<HashKey, Box<dyn Object>>, EvaluatorError> {
+        let value = self.get(index)?;
+        match value.as_any().downcast_ref::<Hash>() {
+            Some(hash) => Ok(&hash.pairs),
+            None => Err(type_error("hash", value)),
+        }
+    }
+
+    /// Returns the hash key of the argument at `index`, or an error if it can't be used as a key.
+    pub fn key(&self, index: usize) -> Result<HashKey, EvaluatorError> {
+        hash_key(self.get(index)?)
+    }
+
+    /// Builds an error for an argument that has the right type but an invalid value.
+    pub fn invalid(&self, reason: &str) -> EvaluatorError {
+        EvaluatorError::new(EvaluatorErrorKind::InvalidArgument {
+            function: self.function.clone(),
+            reason: reason.to_string(),
+        })
+    }
+}
+
+fn type_error(expected: &'static str, found: &dyn Object) -> EvaluatorError {
+    EvaluatorError::new(EvaluatorErrorKind::TypeMismatch {
+        expected,
+        found: found.type_name(),
+    })
+}
+
+/// Returns the builtin functions every evaluator starts with.
+///
+/// Monkey Lang supports the following builtin functions:
+/// - len: Returns the length of an array, or the number of characters in a string
+/// - print: Prints the input argument to stdout
+/// - println: Prints the input argument to stdout, then prints newline character
+/// - push: Expects an array and an object. Returns a new array with the object appended to the end
+/// - tail: Expects a non-empty array. Returns a new array without its first element
+/// - keys: Returns an array of the keys of a hash
+/// - values: Returns an array of the values of a hash, in the same order as `keys`
+/// - has_key: Expects a hash and a key. Returns whether the hash contains the key
+/// - insert: Expects a hash, a key and a value. Returns a new hash with the key set to the value
+/// - remove: Expects a hash and a key. Returns a new hash without the key
+/// - int: Converts a number to an integer, rounding towards zero
+/// - float: Converts a number to a float
+/// - round, floor, ceil: Round a float to the nearest, next lower or next higher whole number.
+///   Integers are returned unchanged
+/// - split: Expects a string and a separator. Returns an array of the parts of the string between
+///   occurrences of the separator
+/// - join: Expects an array of strings and a separator. Returns the strings joined by the separator
+/// - trim: Returns a string without leading and trailing whitespace
+/// - upper, lower: Return a string converted to upper or lower case
+/// - contains: Expects two strings. Returns whether the first contains the second
+/// - replace: Expects a string, a pattern and a replacement. Returns the string with every
+///   occurrence of the pattern replaced
+/// - starts_with, ends_with: Expect two strings. Return whether the first starts or ends with the
+///   second
+/// - chars: Returns an array of the characters of a string, each as a string
+/// - to_string: Converts a value to a string, the same way `print` shows it
+/// - parse_int: Parses a string containing a decimal integer
+/// - range: Expects two integers. Returns the range of integers from the first up to but not
+///   including the second, for use in for loops
+pub(crate) fn standard_builtins() -> Vec<BuiltinFn> {
+    vec![
+        BuiltinFn::new("len", Arity::Exact(1), |args| {
+            let value = args.get(0)?;
+            if let Some(array) = value.as_any().downcast_ref::<Array>() {
+                Ok((array.items.len() as i64).into_object())
+            } else if let Some(string) = value.as_any().downcast_ref::<StringObject>() {
+                Ok((string.value.chars().count() as i64).into_object())
+            } else {
+                Err(type_error("array or string", value))
+            }
+        }),
+        BuiltinFn::new("print", Arity::Exact(1), |args| {
+            print!("{}", args.get(0)?.display());
+            Ok(().into_object())
+        }),
+        BuiltinFn::new("println", Arity::Exact(1), |args| {
+            println!("{}", args.get(0)?.display());
+            Ok(().into_object())
+        }),
+        BuiltinFn::new("push", Arity::Exact(2), |args| {
+            let mut new_array_items = args.array(0)?.to_vec();
+            new_array_items.push(dyn_clone::clone_box(args.get(1)?));
+            Ok(new_array_items.into_object())
+        }),
+        BuiltinFn::new("tail", Arity::Exact(1), |args| {
+            // Very inefficient since removing first element from Vec is O(n)
+            let mut new_array_items = args.array(0)?.to_vec();
+            if new_array_items.is_empty() {
+                Err(args.invalid("expected a non-empty array"))
+            } else {
+                new_array_items.remove(0);
+                Ok(new_array_items.into_object())
+            }
+        }),
+        BuiltinFn::new("keys", Arity::Exact(1), |args| {
+            let keys: Vec<_> = args.hash(0)?.keys().map(HashKey::to_object).collect();
+            Ok(keys.into_object())
+        }),
+        BuiltinFn::new("values", Arity::Exact(1), |args| {
+            Ok(args
+                .hash(0)?
+                .values()
+                .cloned()
+                .collect::<Vec<_>>()
+                .into_object())
+        }),
+        BuiltinFn::new("has_key", Arity::Exact(2), |args| {
+            Ok(args.hash(0)?.contains_key(&args.key(1)?).into_object())
+        }),
+        BuiltinFn::new("insert", Arity::Exact(3), |args| {
+            let mut pairs = args.hash(0)?.clone();
+            pairs.insert(args.key(1)?, dyn_clone::clone_box(args.get(2)?));
+            Ok(Box::new(Hash::new(pairs)))
+        }),
+        BuiltinFn::new("remove", Arity::Exact(2), |args| {
+            let mut pairs = args.hash(0)?.clone();
+            pairs.remove(&args.key(1)?);
+            Ok(Box::new(Hash::new(pairs)))
+        }),
+        BuiltinFn::new("int", Arity::Exact(1), |args| {
+            if is_integer(args.get(0)?) {
+                return Ok(dyn_clone::clone_box(args.get(0)?));
+            }
+            let value = args.number(0)?.trunc();
+            // Casting to i64 saturates, so values out of range have to be caught beforehand
+            if value.is_nan() || value < i64::MIN as f64 || value >= i64::MAX as f64 {
+                Err(args.invalid(&format!("{value:e} is out of range for an integer")))
+            } else {
+                Ok((value as i64).into_object())
+            }
+        }),
+        BuiltinFn::new("float", Arity::Exact(1), |args| {
+            Ok(args.number(0)?.into_object())
+        }),
+        BuiltinFn::new("round", Arity::Exact(1), |args| {
+            round_with(&args, f64::round)
+        }),
+        BuiltinFn::new("floor", Arity::Exact(1), |args| {
+            round_with(&args, f64::floor)
+        }),
+        BuiltinFn::new("ceil", Arity::Exact(1), |args| round_with(&args, f64::ceil)),
+        BuiltinFn::new("split", Arity::Exact(2), |args| {
+            let separator = args.string(1)?;
+            if separator.is_empty() {
+                return Err(args.invalid("the separator can't be empty, use `chars` instead"));
+            }
+            let string = args.string(0)?;
+            let count = string.matches(separator).count() + 1;
+            memory::reserve(string_array_size(count, string.len()))?;
+            Ok(string.split(separator).collect::<Vec<_>>().into_object())
+        }),
+        BuiltinFn::new("join", Arity::Exact(2), |args| {
+            let strings: Vec<String> = args.get_as(0)?;
+            let separator = args.string(1)?;
+            let separators = separator.len() * strings.len().saturating_sub(1);
+            memory::reserve(strings.iter().map(String::len).sum::<usize>() + separators)?;
+            Ok(strings.join(separator).into_object())
+        }),
+        BuiltinFn::new("trim", Arity::Exact(1), |args| {
+            Ok(args.string(0)?.trim().into_object())
+        }),
+        BuiltinFn::new("upper", Arity::Exact(1), |args| {
+            Ok(args.string(0)?.to_uppercase().into_object())
+        }),
+        BuiltinFn::new("lower", Arity::Exact(1), |args| {
+            Ok(args.string(0)?.to_lowercase().into_object())
+        }),
+        BuiltinFn::new("contains", Arity::Exact(2), |args| {
+            Ok(args.string(0)?.contains(args.string(1)?).into_object())
+        }),
+        BuiltinFn::new("replace", Arity::Exact(3), |args| {
+            let pattern = args.string(1)?;
+            if pattern.is_empty() {
+                return Err(args.invalid("the pattern can't be empty"));
+            }
+            let (string, replacement) = (args.string(0)?, args.string(2)?);
+            let count = string.matches(pattern).count();
+            memory::reserve(string.len() - count * pattern.len() + count * replacement.len())?;
+            Ok(string.replace(pattern, replacement).into_object())
+        }),
+        BuiltinFn::new("starts_with", Arity::Exact(2), |args| {
+            Ok(args.string(0)?.starts_with(args.string(1)?).into_object())
+        }),
+        BuiltinFn::new("ends_with", Arity::Exact(2), |args| {
+            Ok(args.string(0)?.ends_with(args.string(1)?).into_object())
+        }),
+        BuiltinFn::new("chars", Arity::Exact(1), |args| {
+            let string = args.string(0)?;
+            memory::reserve(string_array_size(string.chars().count(), string.len()))?;
+            Ok(string
+                .chars()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .into_object())
+        }),
+        BuiltinFn::new("to_string", Arity::Exact(1), |args| {
+            Ok(args.get(0)?.display().into_object())
+        }),
+        BuiltinFn::new("parse_int", Arity::Exact(1), |args| {
+            let string = args.string(0)?;
+            match string.parse::<i64>() {
+                Ok(value) => Ok(value.into_object()),
+                Err(_) => Err(args.invalid(&format!(
+                    "{} is not a valid integer",
+                    args.get(0)?.inspect()
+                ))),
+            }
+        }),
+        BuiltinFn::new("range", Arity::Exact(2), |args| {
+            Ok(Box::new(Range::new(args.integer(0)?, args.integer(1)?)))
+        }),
+    ]
+}
+
+// Applies `round` to the first argument if it is a float. Integers are already whole numbers, so
+// they are returned unchanged.
+fn round_with(args: &Arguments, round: fn(f64) -> f64) -> Result<Box<dyn Object>, EvaluatorError> {
+    let value = args.get(0)?;
+    if is_integer(value) {
+        Ok(dyn_clone::clone_box(value))
+    } else {
+        Ok(round(args.number(0)?).into_object())
+    }
+}
+
+fn is_integer(value: &dyn Object) -> bool {
+    value.as_any().is::<Integer>() || value.as_any().is::<BigInteger>()
+}
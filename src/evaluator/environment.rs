@@ -1,37 +1,301 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::mem;
+use std::rc::{Rc, Weak};
 
-use crate::{ast::Identifier, object::Object};
+use crate::object::memory::{Allocation, boxed_size};
+use crate::{ast::Identifier, object::Object, token::Span};
 
-#[derive(Clone)]
+/// A scope of variable bindings. `Environment` is a handle to a shared scope: cloning it is cheap
+/// and the clone refers to the same bindings, which is how closures capture the scope they were
+/// created in. Bindings added to a scope after a closure was created are visible to the closure.
+#[derive(Clone, Default)]
 pub struct Environment {
-    mapping: HashMap<String, Box<dyn Object>>,
-    outer: Option<Box<Environment>>,
+    scope: Rc<RefCell<Scope>>,
 }
 
-impl Environment {
-    pub fn new() -> Self {
+struct Scope {
+    mapping: HashMap<String, Binding>,
+    outer: Option<Environment>,
+    // Whether the scope is in `CAPTURED_SCOPES`
+    captured: bool,
+    _allocation: Allocation,
+}
+
+impl Scope {
+    fn new(outer: Option<Environment>) -> Self {
         Self {
             mapping: HashMap::new(),
-            outer: None,
+            outer,
+            captured: false,
+            _allocation: Allocation::new(mem::size_of::<Scope>()),
         }
     }
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+struct Binding {
+    value: Box<dyn Object>,
+    // Only bindings made with `var` can be assigned to
+    mutable: bool,
+    // Where the binding was made in the source, or `None` for bindings made from Rust
+    declared_at: Option<Span>,
+    // The memory taken by the binding's entry in its scope
+    _allocation: Allocation,
+}
+
+// The number of captured scopes below which `collect_cycles_if_due` never collects
+const MIN_COLLECTION_THRESHOLD: usize = 1024;
+
+thread_local! {
+    // The scopes on this thread that closures have captured, along with their outer scopes. Only
+    // closures make references from a scope to one of its nested scopes, so every reference cycle
+    // between scopes goes through these.
+    static CAPTURED_SCOPES: RefCell<CapturedScopes> = const {
+        RefCell::new(CapturedScopes {
+            scopes: Vec::new(),
+            threshold: MIN_COLLECTION_THRESHOLD,
+        })
+    };
+}
 
+struct CapturedScopes {
+    scopes: Vec<Weak<RefCell<Scope>>>,
+    // `collect_cycles_if_due` collects once there are this many scopes
+    threshold: usize,
+}
+
+/// The reasons `Environment::assign` can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssignError {
+    /// The name isn't bound in the environment or in any of its outer environments.
+    Undeclared,
+    /// The nearest binding of the name is immutable. `declared_at` is where it was bound, or
+    /// `None` if it was bound from Rust.
+    Immutable { declared_at: Option<Span> },
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new scope nested in `outer`. Lookups that aren't bound in the new scope fall
+    /// back to `outer`.
     pub fn new_wrapped(outer: &Environment) -> Self {
         Self {
-            mapping: HashMap::new(),
-            outer: Some(Box::new(outer.clone())),
+            scope: Rc::new(RefCell::new(Scope::new(Some(outer.clone())))),
+        }
+    }
+
+    /// Returns a number that identifies the scope. Handles to the same scope have the same
+    /// address, and the address of a scope doesn't change while it is alive.
+    pub(crate) fn address(&self) -> usize {
+        Rc::as_ptr(&self.scope) as usize
+    }
+
+    /// Registers this scope and its outer scopes with the cycle collector. Called when a closure
+    /// captures the environment.
+    pub(crate) fn capture(&self) {
+        let mut scope = self.scope.clone();
+        loop {
+            let outer = {
+                let mut borrowed = scope.borrow_mut();
+                // The outer scopes of a captured scope are always captured as well
+                if borrowed.captured {
+                    return;
+                }
+                borrowed.captured = true;
+                borrowed.outer.as_ref().map(|outer| outer.scope.clone())
+            };
+            CAPTURED_SCOPES
+                .with(|captured| captured.borrow_mut().scopes.push(Rc::downgrade(&scope)));
+            match outer {
+                Some(outer) => scope = outer,
+                None => return,
+            }
         }
     }
 
+    /// Binds `id` to `value` in this environment as an immutable binding, like `let` does.
     pub fn insert(&mut self, id: &Identifier, value: Box<dyn Object>) {
-        self.mapping.insert(id.value.clone(), value);
+        self.bind(&id.value, value, false, Some(id.span));
+    }
+
+    /// Binds `id` to `value` in this environment as a binding that can be assigned to, like `var`
+    /// does.
+    pub fn insert_mutable(&mut self, id: &Identifier, value: Box<dyn Object>) {
+        self.bind(&id.value, value, true, Some(id.span));
+    }
+
+    /// Binds `name` to `value` in this environment, shadowing any binding in the outer
+    /// environments. The binding is immutable, so Donkey code can't assign to it.
+    pub fn set(&mut self, name: &str, value: Box<dyn Object>) {
+        self.bind(name, value, false, None);
+    }
+
+    fn bind(
+        &mut self,
+        name: &str,
+        value: Box<dyn Object>,
+        mutable: bool,
+        declared_at: Option<Span>,
+    ) {
+        let entry_size = mem::size_of::<(String, Binding)>() + name.len();
+        let binding = Binding {
+            _allocation: Allocation::new(entry_size + boxed_size(value.as_ref())),
+            value,
+            mutable,
+            declared_at,
+        };
+        // The previous value is dropped after the scope is released, since dropping a closure
+        // can release other handles to this scope
+        let _previous = self
+            .scope
+            .borrow_mut()
+            .mapping
+            .insert(name.to_string(), binding);
+    }
+
+    /// Replaces the value of the nearest binding of `name`, looking in this environment and then
+    /// in the outer environments. Nothing changes if `name` isn't bound or if its nearest binding
+    /// is immutable.
+    pub fn assign(&mut self, name: &str, value: Box<dyn Object>) -> Result<(), AssignError> {
+        let mut scope = self.scope.borrow_mut();
+        if let Some(binding) = scope.mapping.get_mut(name) {
+            if !binding.mutable {
+                return Err(AssignError::Immutable {
+                    declared_at: binding.declared_at,
+                });
+            }
+            // Like in `bind`, the previous value is dropped after the scope is released
+            let _previous = mem::replace(&mut binding.value, value);
+            drop(scope);
+            return Ok(());
+        }
+        match scope.outer.as_mut() {
+            Some(outer) => outer.assign(name, value),
+            None => Err(AssignError::Undeclared),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<Box<dyn Object>> {
+        let scope = self.scope.borrow();
+        match scope.mapping.get(id) {
+            Some(binding) => Some(binding.value.clone()),
+            None => scope.outer.as_ref().and_then(|outer| outer.get(id)),
+        }
     }
 
-    pub fn get(&self, id: &str) -> Option<&Box<dyn Object>> {
-        self.mapping.get(id).or_else(|| {
-            self.outer
-                .as_ref()
-                .and_then(|environment| environment.get(id))
+    /// Removes every binding from this environment. Functions hold on to the environment they
+    /// were defined in, so a function bound in its own environment forms a reference cycle. The
+    /// evaluator frees such cycles once they are unreachable, but clearing the environment breaks
+    /// them right away.
+    pub fn clear(&mut self) {
+        let _mapping = mem::take(&mut self.scope.borrow_mut().mapping);
+    }
+}
+
+/// Calls `collect_cycles` if enough scopes were captured since the last collection. The threshold
+/// grows with the number of scopes that survive a collection, so the work of collecting is
+/// proportional to the number of scopes captured.
+pub(crate) fn collect_cycles_if_due() {
+    let due = CAPTURED_SCOPES.with(|captured| {
+        let captured = captured.borrow();
+        captured.scopes.len() >= captured.threshold
+    });
+    if due {
+        collect_cycles();
+    }
+}
+
+/// Frees the scopes that are only kept alive by reference cycles. A closure holds on to the scope
+/// it was created in, so storing it in that scope or in a scope nested in it makes a cycle that
+/// reference counting alone never frees.
+///
+/// Cycles are found by trial deletion. The references that captured scopes hold to each other,
+/// through their outer scopes and the values bound in them, are subtracted from their reference
+/// counts. Scopes with references left over are used from outside of the captured scopes, like by
+/// the interpreter's globals or by values the evaluator is working with, so they are kept along
+/// with every scope they refer to. The other scopes are unreachable and are cleared, which breaks
+/// their cycles.
+///
+/// This must not be called while a scope is borrowed, which is never the case between the
+/// evaluation of two AST nodes.
+pub(crate) fn collect_cycles() {
+    let scopes: Vec<Rc<RefCell<Scope>>> = CAPTURED_SCOPES
+        .with(|captured| mem::take(&mut captured.borrow_mut().scopes))
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    let indices: HashMap<usize, usize> = scopes
+        .iter()
+        .enumerate()
+        .map(|(index, scope)| (Rc::as_ptr(scope) as usize, index))
+        .collect();
+
+    // The indices of the scopes that each scope refers to, once for every reference. Every scope
+    // that a captured scope refers to is captured as well.
+    let references: Vec<Vec<usize>> = scopes
+        .iter()
+        .map(|scope| {
+            let scope = scope.borrow();
+            let mut referenced = Vec::new();
+            let mut visit = |env: &Environment| {
+                if let Some(&index) = indices.get(&env.address()) {
+                    referenced.push(index);
+                }
+            };
+            if let Some(outer) = &scope.outer {
+                visit(outer);
+            }
+            for binding in scope.mapping.values() {
+                binding.value.visit_environments(&mut visit);
+            }
+            referenced
         })
+        .collect();
+    let mut internal_references = vec![0; scopes.len()];
+    for &index in references.iter().flatten() {
+        internal_references[index] += 1;
+    }
+
+    // `scopes` holds one of the references to each scope as well
+    let mut reachable: Vec<bool> = scopes
+        .iter()
+        .zip(&internal_references)
+        .map(|(scope, internal)| Rc::strong_count(scope) > internal + 1)
+        .collect();
+    let mut pending: Vec<usize> = (0..scopes.len()).filter(|&i| reachable[i]).collect();
+    while let Some(index) = pending.pop() {
+        for &referenced in &references[index] {
+            if !reachable[referenced] {
+                reachable[referenced] = true;
+                pending.push(referenced);
+            }
+        }
+    }
+
+    let mut survivors = Vec::new();
+    let mut garbage = Vec::new();
+    for (scope, reachable) in scopes.iter().zip(reachable) {
+        if reachable {
+            survivors.push(Rc::downgrade(scope));
+        } else {
+            garbage.push(mem::take(&mut scope.borrow_mut().mapping));
+        }
     }
+    CAPTURED_SCOPES.with(|captured| {
+        let mut captured = captured.borrow_mut();
+        captured.threshold = MIN_COLLECTION_THRESHOLD.max(2 * survivors.len());
+        captured.scopes = survivors;
+    });
+    // The garbage is dropped once the captured scopes aren't borrowed, since dropping it drops
+    // the scopes it refers to
+    drop(garbage);
 }
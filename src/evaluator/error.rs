@@ -0,0 +1,341 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::evaluator::builtins::Arity;
+use crate::token::Span;
+
+/// The different kinds of errors that can happen while evaluating a program. Each kind has a
+/// stable error code (see `EvaluatorErrorKind::code`) that tooling can match on instead of the
+/// error message.
+///
+/// Types are referred to by the names returned from `Object::type_name`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvaluatorErrorKind {
+    /// An identifier that isn't bound in the environment or as a builtin.
+    UnknownIdentifier {
+        name: String,
+    },
+    /// A value had a different type than the operation needed.
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// An array or string was indexed outside of its bounds.
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+    },
+    /// A function was called with the wrong number of arguments. `function` is `None` for
+    /// anonymous functions.
+    ArityMismatch {
+        function: Option<String>,
+        expected: Arity,
+        found: usize,
+    },
+    DivisionByZero,
+    /// The right operand of `%` was zero.
+    ModuloByZero,
+    /// Something other than a function was called.
+    NotCallable {
+        found: &'static str,
+    },
+    /// An operator that isn't defined for its operands.
+    UnknownOperator {
+        operator: String,
+    },
+    /// A builtin function received an argument of the right type but with an invalid value.
+    InvalidArgument {
+        function: String,
+        reason: String,
+    },
+    /// The evaluator doesn't know how to evaluate a type of AST node.
+    UnknownNode,
+    /// A value that can't be used as a hash key was used as one.
+    UnhashableKey {
+        found: &'static str,
+    },
+    /// A hash was indexed with a key it doesn't contain. `key` is the inspected key.
+    KeyNotFound {
+        key: String,
+    },
+    /// The result of integer arithmetic didn't fit in an `i64`. Only raised when the evaluator's
+    /// `OverflowBehavior` is `Error`.
+    IntegerOverflow {
+        operation: String,
+    },
+    /// A variable that isn't bound was assigned to.
+    UndeclaredAssignment {
+        name: String,
+    },
+    /// A variable bound with `let`, or another immutable binding, was assigned to.
+    /// `declared_at` is where the variable was bound, or `None` if it was bound from Rust.
+    ImmutableAssignment {
+        name: String,
+        declared_at: Option<Span>,
+    },
+    /// The bounds of a slice of an array or string were out of order or outside of it.
+    InvalidSlice {
+        start: i64,
+        end: i64,
+        len: usize,
+    },
+    /// A call would have nested more Donkey function calls than allowed. `depth` is the number of
+    /// calls that were in progress.
+    RecursionLimitExceeded {
+        depth: usize,
+    },
+    /// Evaluation took more than the `limit` steps it was allowed to take.
+    ExecutionLimitExceeded {
+        limit: u64,
+    },
+    /// Evaluation was stopped through a `CancellationHandle`.
+    Cancelled,
+    /// The objects created by the evaluator took up more than `limit` bytes.
+    MemoryLimitExceeded {
+        limit: usize,
+    },
+    /// Evaluation used more than the `limit` bytes of native stack it was allowed to use. `depth`
+    /// is the number of calls that were in progress.
+    StackLimitExceeded {
+        depth: usize,
+        limit: usize,
+    },
+}
+
+impl EvaluatorErrorKind {
+    /// Returns the stable error code for this kind of error. Runtime error codes start with `R`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvaluatorErrorKind::UnknownIdentifier { .. } => "R0001",
+            EvaluatorErrorKind::TypeMismatch { .. } => "R0002",
+            EvaluatorErrorKind::IndexOutOfBounds { .. } => "R0003",
+            EvaluatorErrorKind::ArityMismatch { .. } => "R0004",
+            EvaluatorErrorKind::DivisionByZero => "R0005",
+            EvaluatorErrorKind::NotCallable { .. } => "R0006",
+            EvaluatorErrorKind::UnknownOperator { .. } => "R0007",
+            EvaluatorErrorKind::InvalidArgument { .. } => "R0008",
+            EvaluatorErrorKind::UnknownNode => "R0009",
+            EvaluatorErrorKind::UnhashableKey { .. } => "R0010",
+            EvaluatorErrorKind::KeyNotFound { .. } => "R0011",
+            EvaluatorErrorKind::IntegerOverflow { .. } => "R0012",
+            EvaluatorErrorKind::InvalidSlice { .. } => "R0013",
+            EvaluatorErrorKind::ModuloByZero => "R0014",
+            EvaluatorErrorKind::UndeclaredAssignment { .. } => "R0015",
+            EvaluatorErrorKind::ImmutableAssignment { .. } => "R0016",
+            EvaluatorErrorKind::RecursionLimitExceeded { .. } => "R0017",
+            EvaluatorErrorKind::ExecutionLimitExceeded { .. } => "R0018",
+            EvaluatorErrorKind::Cancelled => "R0019",
+            EvaluatorErrorKind::MemoryLimitExceeded { .. } => "R0020",
+            EvaluatorErrorKind::StackLimitExceeded { .. } => "R0021",
+        }
+    }
+}
+
+impl fmt::Display for EvaluatorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluatorErrorKind::UnknownIdentifier { name } => {
+                write!(f, "Unknown identifier found: {name}")
+            }
+            EvaluatorErrorKind::TypeMismatch { expected, found } => {
+                write!(f, "Expected {expected}, found {found} instead")
+            }
+            EvaluatorErrorKind::IndexOutOfBounds { index, len } => write!(
+                f,
+                "Out of bounds access. Index is {index} but length is {len}"
+            ),
+            EvaluatorErrorKind::ArityMismatch {
+                function,
+                expected,
+                found,
+            } => match function {
+                Some(function) => write!(
+                    f,
+                    "{function} expects {expected} argument(s), but {found} were given"
+                ),
+                None => write!(
+                    f,
+                    "Function expects {expected} argument(s), but {found} were given"
+                ),
+            },
+            EvaluatorErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            EvaluatorErrorKind::ModuloByZero => write!(f, "Modulo by zero"),
+            EvaluatorErrorKind::NotCallable { found } => {
+                write!(f, "Expected a function in call expression, found {found}")
+            }
+            EvaluatorErrorKind::UnknownOperator { operator } => {
+                write!(f, "Unknown operator: {operator}")
+            }
+            EvaluatorErrorKind::InvalidArgument { function, reason } => {
+                write!(f, "Invalid argument to {function}: {reason}")
+            }
+            EvaluatorErrorKind::UnknownNode => {
+                write!(f, "Evaluator encountered unknown AST type")
+            }
+            EvaluatorErrorKind::UnhashableKey { found } => {
+                write!(
+                    f,
+                    "Expected integer, string or boolean as hash key, found {found}"
+                )
+            }
+            EvaluatorErrorKind::KeyNotFound { key } => write!(f, "Key not found in hash: {key}"),
+            EvaluatorErrorKind::IntegerOverflow { operation } => {
+                write!(f, "Integer overflow in {operation}")
+            }
+            EvaluatorErrorKind::UndeclaredAssignment { name } => {
+                write!(f, "Assignment to undeclared variable: {name}")
+            }
+            EvaluatorErrorKind::ImmutableAssignment { name, declared_at } => match declared_at {
+                Some(span) => write!(
+                    f,
+                    "Assignment to immutable variable: {name}, declared at {}:{}",
+                    span.start.line, span.start.column
+                ),
+                None => write!(f, "Assignment to immutable variable: {name}"),
+            },
+            EvaluatorErrorKind::InvalidSlice { start, end, len } => {
+                write!(f, "Invalid slice [{start}:{end}] of length {len}")
+            }
+            EvaluatorErrorKind::RecursionLimitExceeded { depth } => {
+                write!(f, "Maximum recursion depth exceeded ({depth} nested calls)")
+            }
+            EvaluatorErrorKind::ExecutionLimitExceeded { limit } => {
+                write!(f, "Execution limit of {limit} steps exceeded")
+            }
+            EvaluatorErrorKind::Cancelled => write!(f, "Evaluation was cancelled"),
+            EvaluatorErrorKind::MemoryLimitExceeded { limit } => {
+                write!(f, "Memory limit of {limit} bytes exceeded")
+            }
+            EvaluatorErrorKind::StackLimitExceeded { depth, limit } => write!(
+                f,
+                "Stack limit of {limit} bytes exceeded ({depth} nested calls)"
+            ),
+        }
+    }
+}
+
+/// A function call that was in progress. The evaluator keeps a stack of these so runtime errors
+/// can report how execution got to the point where the error happened.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    /// The name the called function was bound to with `let`, or `None` for anonymous functions
+    pub function: Option<String>,
+    /// The span of the call expression that created this frame
+    pub call_site: Span,
+}
+
+impl Frame {
+    pub fn new(function: Option<String>, call_site: Span) -> Self {
+        Self {
+            function,
+            call_site,
+        }
+    }
+}
+
+/// An error that happened while evaluating a program. The details are boxed to keep the `Result`s
+/// returned all over the evaluator small, and can be accessed as fields of the error through
+/// `Deref`.
+#[derive(Clone, Debug)]
+pub struct EvaluatorError(Box<EvaluatorErrorDetails>);
+
+#[derive(Clone, Debug)]
+pub struct EvaluatorErrorDetails {
+    pub kind: EvaluatorErrorKind,
+    // The span of the innermost AST node that was being evaluated when the error occurred
+    pub span: Option<Span>,
+    // The calls that were in progress when the error occurred, with the outermost call first.
+    // This is empty for errors that happen outside of any function.
+    pub backtrace: Vec<Frame>,
+}
+
+impl EvaluatorError {
+    pub fn new(kind: EvaluatorErrorKind) -> Self {
+        Self(Box::new(EvaluatorErrorDetails {
+            kind,
+            span: None,
+            backtrace: Vec::new(),
+        }))
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Returns a suggestion for fixing the error, if there is a useful one.
+    pub fn help(&self) -> Option<&'static str> {
+        match self.kind {
+            EvaluatorErrorKind::UnknownIdentifier { .. } => {
+                Some("variables must be bound with `let` before they are used")
+            }
+            EvaluatorErrorKind::IndexOutOfBounds { .. } => {
+                Some("use `len` to check the length of the array or string before indexing it")
+            }
+            EvaluatorErrorKind::InvalidSlice { .. } => Some(
+                "the bounds of a slice must satisfy 0 <= start <= end <= length, where a missing start is 0 and a missing end is the length",
+            ),
+            EvaluatorErrorKind::KeyNotFound { .. } => {
+                Some("use `has_key` to check whether the hash contains a key before indexing it")
+            }
+            EvaluatorErrorKind::UndeclaredAssignment { .. } => {
+                Some("variables must be bound with `var` before they are assigned to")
+            }
+            EvaluatorErrorKind::ImmutableAssignment { .. } => Some(
+                "only variables bound with `var` can be assigned to; copy parameters and loop variables into a `var` to change them",
+            ),
+            EvaluatorErrorKind::RecursionLimitExceeded { .. } => {
+                Some("make the recursive call a tail call, which doesn't count towards the limit")
+            }
+            EvaluatorErrorKind::StackLimitExceeded { .. } => Some(
+                "make the recursive call a tail call, or run the evaluator on a thread with a bigger stack and raise the limit with `set_max_stack_size`",
+            ),
+            _ => None,
+        }
+    }
+
+    /// Attaches `span` to the error, unless the error already has a more specific span.
+    pub(crate) fn or_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    /// Attaches `call_stack` to the error, unless a deeper call stack was already attached.
+    pub(crate) fn or_backtrace(mut self, call_stack: &[Frame]) -> Self {
+        if self.backtrace.is_empty() {
+            self.backtrace = call_stack.to_vec();
+        }
+        self
+    }
+}
+
+impl Deref for EvaluatorError {
+    type Target = EvaluatorErrorDetails;
+
+    fn deref(&self) -> &EvaluatorErrorDetails {
+        &self.0
+    }
+}
+
+impl DerefMut for EvaluatorError {
+    fn deref_mut(&mut self) -> &mut EvaluatorErrorDetails {
+        &mut self.0
+    }
+}
+
+impl fmt::Display for EvaluatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{}:{}: {}",
+                span.start.line, span.start.column, self.kind
+            ),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl Error for EvaluatorError {}
@@ -1,200 +1,382 @@
+pub mod builtins;
 pub mod environment;
+pub mod error;
 
 mod tests;
 
 use std::any::Any;
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::ast::{
-    ArrayExpression, BlockStatement, BooleanLiteral, CallExpression, Expression,
-    ExpressionStatement, FunctionLiteral, Identifier, IfExpression, IndexExpression,
-    InfixExpression, IntegerLiteral, LetStatement, Node, NullLiteral, PrefixExpression, Program,
-    ReturnStatement, Statement, StringExpression,
+    ArrayExpression, AssignExpression, BlockStatement, BooleanLiteral, BreakStatement,
+    CallExpression, ContinueStatement, Expression, ExpressionStatement, FloatLiteral, ForStatement,
+    FunctionLiteral, HashExpression, Identifier, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, InterpolatedStringExpression, LetStatement, Node, NullLiteral,
+    PrefixExpression, Program, ReturnStatement, SliceExpression, Statement, StringExpression,
+    WhileStatement,
 };
-use crate::evaluator::environment::Environment;
+pub use crate::evaluator::builtins::{Arguments, Arity};
+use crate::evaluator::environment::{AssignError, Environment};
+pub use crate::evaluator::error::{
+    EvaluatorError, EvaluatorErrorDetails, EvaluatorErrorKind, Frame,
+};
+use crate::object::memory::{self, MemoryUsage, TrackingGuard};
 use crate::object::{
-    Array, Boolean, BuiltinFn, Function, Integer, Null, Object, ReturnValue, SelfRef, StringObject,
+    Array, BigInt, BigInteger, Boolean, Break, BuiltinFn, Continue, Float, Function, Hash, HashKey,
+    Integer, Null, Object, Range, ReturnValue, StringObject, TailCall,
 };
+use crate::token::Span;
 
-#[derive(Debug)]
-pub struct EvaluatorError {
-    pub error_message: String,
+/// What the evaluator does when the result of integer arithmetic doesn't fit in an `i64`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OverflowBehavior {
+    /// Raise an `IntegerOverflow` error.
+    #[default]
+    Error,
+    /// Return the exact result as a `BigInteger`.
+    Promote,
 }
 
-impl EvaluatorError {
-    fn new(error_message: &str) -> Self {
-        Self {
-            error_message: error_message.to_string(),
-        }
+/// Lets other threads stop an evaluation that is in progress. Clones of a handle share its state,
+/// so a clone can be kept to cancel the evaluation of an evaluator the handle was given to. The
+/// evaluator checks the handle before evaluating each AST node, and fails with a `Cancelled`
+/// error once it has been cancelled.
+///
+/// A handle stays cancelled, so evaluators need a new handle to run code again.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
     }
 }
 
+/// The number of nested Donkey function calls the evaluator allows by default. Each call also
+/// uses native stack space, and with the default stack size of `DEFAULT_MAX_STACK_SIZE` the stack
+/// limit is usually reached first.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// The number of bytes of native stack evaluation can use by default. This leaves room for the
+/// caller on a thread with Rust's default stack size of 2 MiB.
+pub const DEFAULT_MAX_STACK_SIZE: usize = 1024 * 1024;
+
+// Evaluates `$node` for its value. A `return`, `break` or `continue` in it, eg. in a block of
+// an if expression, ends the expression it is in as well, so its signal is returned right away
+// for the enclosing function or loop to handle.
+macro_rules! eval_value {
+    ($evaluator:expr, $node:expr, $env:expr) => {{
+        let value = $evaluator.eval($node, $env)?;
+        if is_signal(value.as_ref()) {
+            return Ok(value);
+        }
+        value
+    }};
+}
+
 pub struct Evaluator {
-    builtin_fns: HashMap<String, Box<dyn Object>>,
-    self_fn: Option<Box<Function>>,
+    builtin_fns: HashMap<String, BuiltinFn>,
+    // The Donkey functions currently being called, with the outermost call first
+    call_stack: Vec<Frame>,
+    max_call_depth: usize,
+    max_stack_size: usize,
+    // The address of the native stack where the outermost evaluation started, while evaluating
+    stack_base: Option<usize>,
+    // The number of steps each outermost evaluation can take, and the number the current one has
+    // taken
+    step_limit: Option<u64>,
+    steps: u64,
+    cancellation_handle: Option<CancellationHandle>,
+    memory_usage: MemoryUsage,
+    overflow_behavior: OverflowBehavior,
 }
 
 impl Evaluator {
     pub fn new() -> Self {
-        let mut builtin_fns: HashMap<String, Box<dyn Object>> = HashMap::new();
-
-        // Define builtin functions here.
-        // Monkey Lang supports the following builtin functions:
-        // - len: Returns the length of an input array or string
-        // - print: Prints the input argument to stdout
-        // - println: Prints the input argument to stdout, then prints newline character
-        // - push: Expects an array and an object. Returns a new array with the object appended to
-        // the end
-        builtin_fns.insert(
-            "len".to_string(),
-            Box::new(BuiltinFn::new(Rc::new(|args| {
-                if args.len() != 1 {
-                    Err(EvaluatorError::new("len expects exactly one argument"))
-                } else if let Some(array_expression) = args[0].as_any().downcast_ref::<Array>() {
-                    Ok(Box::new(Integer::new(array_expression.items.len() as i64)))
-                } else {
-                    Err(EvaluatorError::new(
-                        "Builtin function len expects array argument",
-                    ))
-                }
-            }))),
-        );
-        builtin_fns.insert(
-            "print".to_string(),
-            Box::new(BuiltinFn::new(Rc::new(|args| {
-                if args.len() != 1 {
-                    Err(EvaluatorError::new("print expects exactly one argument"))
-                } else {
-                    print!("{}", args[0].inspect());
-                    Ok(Box::new(Null::new()))
-                }
-            }))),
-        );
-        builtin_fns.insert(
-            "println".to_string(),
-            Box::new(BuiltinFn::new(Rc::new(|args| {
-                if args.len() != 1 {
-                    Err(EvaluatorError::new("print expects exactly one argument"))
-                } else {
-                    println!("{}", args[0].inspect());
-                    Ok(Box::new(Null::new()))
-                }
-            }))),
-        );
-        builtin_fns.insert(
-            "push".to_string(),
-            Box::new(BuiltinFn::new(Rc::new(|args| {
-                if args.len() != 2 {
-                    Err(EvaluatorError::new("push expects exactly two arguments"))
-                } else if let Some(array_expression) = args[0].as_any().downcast_ref::<Array>() {
-                    let mut new_array_items = array_expression.items.clone();
-                    new_array_items.push(args[1].clone());
-                    Ok(Box::new(Array::new(new_array_items)))
-                } else {
-                    Err(EvaluatorError::new(
-                        "push expects the first argument to be an array",
-                    ))
-                }
-            }))),
-        );
-        builtin_fns.insert(
-            "tail".to_string(),
-            Box::new(BuiltinFn::new(Rc::new(|args| {
-                if args.len() != 1 {
-                    Err(EvaluatorError::new("tail expects exactly one arguments"))
-                } else if let Some(array_expression) = args[0].as_any().downcast_ref::<Array>() {
-                    // Very inefficient since removing first element from Vec is O(n)
-                    let mut new_array_items = array_expression.items.clone();
-                    if new_array_items.is_empty() {
-                        Err(EvaluatorError::new("tail expects on a non-empty list"))
-                    } else {
-                        new_array_items.remove(0);
-                        Ok(Box::new(Array::new(new_array_items)))
-                    }
-                } else {
-                    Err(EvaluatorError::new(
-                        "tail expects the argument to be an array",
-                    ))
-                }
-            }))),
-        );
-        Self {
-            builtin_fns,
-            self_fn: None,
+        let mut evaluator = Self {
+            builtin_fns: HashMap::new(),
+            call_stack: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_stack_size: DEFAULT_MAX_STACK_SIZE,
+            stack_base: None,
+            step_limit: None,
+            steps: 0,
+            cancellation_handle: None,
+            memory_usage: MemoryUsage::new(),
+            overflow_behavior: OverflowBehavior::default(),
+        };
+        for builtin_fn in builtins::standard_builtins() {
+            evaluator.register_builtin(builtin_fn);
         }
+        evaluator
+    }
+
+    /// Makes `builtin_fn` callable from Donkey code under its name. Registering a builtin with
+    /// the same name as an existing builtin replaces it.
+    pub fn register_builtin(&mut self, builtin_fn: BuiltinFn) {
+        self.builtin_fns.insert(builtin_fn.name.clone(), builtin_fn);
+    }
+
+    /// Sets what happens when integer arithmetic overflows. Overflow is an error by default.
+    pub fn set_overflow_behavior(&mut self, overflow_behavior: OverflowBehavior) {
+        self.overflow_behavior = overflow_behavior;
+    }
+
+    /// Sets how many Donkey function calls can be nested before the evaluator raises a
+    /// `RecursionLimitExceeded` error. Tail calls replace the call they are made from, so they
+    /// don't count towards the limit.
+    ///
+    /// Every nested call uses native stack space, so evaluation also stops with a
+    /// `StackLimitExceeded` error once it has used the stack size set by `set_max_stack_size`, no
+    /// matter how many calls are nested. With the default stack size that usually happens long
+    /// before the default call depth is reached.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Sets how many bytes of native stack evaluation can use before the evaluator raises a
+    /// `StackLimitExceeded` error, so that deep recursion doesn't overflow the stack and abort
+    /// the process. The stack is measured from where the outermost call to `eval` or
+    /// `call_function` starts, so the limit should leave room for the stack the caller uses on the
+    /// thread the evaluator runs on.
+    ///
+    /// The default of `DEFAULT_MAX_STACK_SIZE` fits a thread with Rust's default stack size. It's
+    /// enough for about a hundred nested calls in debug builds and a few hundred in release
+    /// builds, so threads with bigger stacks can raise it to allow deeper recursion.
+    pub fn set_max_stack_size(&mut self, max_stack_size: usize) {
+        self.max_stack_size = max_stack_size;
+    }
+
+    /// Limits how many steps evaluation can take before it fails with an `ExecutionLimitExceeded`
+    /// error, or removes the limit if `step_limit` is `None`. Evaluating an AST node and running
+    /// an iteration of a loop are a step each. The limit applies to each outermost call to `eval`
+    /// or `call_function` on its own, so every program the evaluator runs gets the full budget.
+    pub fn set_step_limit(&mut self, step_limit: Option<u64>) {
+        self.step_limit = step_limit;
+    }
+
+    /// Makes evaluation fail with a `Cancelled` error once `cancellation_handle` is cancelled.
+    pub fn set_cancellation_handle(&mut self, cancellation_handle: CancellationHandle) {
+        self.cancellation_handle = Some(cancellation_handle);
+    }
+
+    /// Limits the approximate number of bytes that the objects and environments created by the
+    /// evaluator can take up while they are alive, or removes the limit if `memory_limit` is
+    /// `None`. Evaluation fails with a `MemoryLimitExceeded` error when they would take up more.
+    /// Memory is only counted while a limit is set.
+    pub fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_usage.set_limit(memory_limit);
+    }
+
+    /// Returns the approximate number of bytes taken up by the live objects that were created
+    /// while a memory limit was set.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage.bytes()
     }
 
     pub fn eval<T: Node + ?Sized>(
         &mut self,
         node: &T,
         env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        if self.stack_base.is_none() {
+            return self.run_outermost(|evaluator| evaluator.eval(node, env));
+        }
+        let _tracking = self.track_memory();
+        self.step()
+            .and_then(|()| self.eval_node(node, env))
+            .map_err(|error| error.or_span(node.span()))
+    }
+
+    // Runs `evaluate` as the outermost evaluation, which gets a step budget of its own and which
+    // the native stack used by evaluation is measured from
+    fn run_outermost<R>(&mut self, evaluate: impl FnOnce(&mut Self) -> R) -> R {
+        self.steps = 0;
+        self.stack_base = Some(stack_address());
+        let result = evaluate(self);
+        self.stack_base = None;
+        result
+    }
+
+    // Charges the objects created until the returned guard is dropped to the evaluator, if its
+    // memory is limited
+    fn track_memory(&self) -> Option<TrackingGuard> {
+        self.memory_usage.limit().map(|_| self.memory_usage.track())
+    }
+
+    // Counts a step of evaluation, and stops evaluation if it has run out of steps or memory, or
+    // has been cancelled
+    fn step(&mut self) -> Result<(), EvaluatorError> {
+        if let Some(step_limit) = self.step_limit {
+            if self.steps >= step_limit {
+                return Err(EvaluatorError::new(
+                    EvaluatorErrorKind::ExecutionLimitExceeded { limit: step_limit },
+                ));
+            }
+            self.steps += 1;
+        }
+        // Overflowing the native stack would abort the process, so deep recursion is an error
+        // instead
+        if let Some(stack_base) = self.stack_base
+            && stack_base.abs_diff(stack_address()) > self.max_stack_size
+        {
+            return Err(EvaluatorError::new(
+                EvaluatorErrorKind::StackLimitExceeded {
+                    depth: self.call_stack.len(),
+                    limit: self.max_stack_size,
+                },
+            ));
+        }
+        if self
+            .cancellation_handle
+            .as_ref()
+            .is_some_and(|handle| handle.is_cancelled())
+        {
+            return Err(EvaluatorError::new(EvaluatorErrorKind::Cancelled));
+        }
+        self.memory_usage.check(0)
+    }
+
+    fn eval_node<T: Node + ?Sized>(
+        &mut self,
+        node: &T,
+        env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
         if let Some(program) = node.as_any().downcast_ref::<Program>() {
-            self.eval_block_statement(&program.statements, env, true)
+            self.eval_block_statement(&program.statements, env, true, false)
         } else if let Some(statement) = node.as_any().downcast_ref::<ExpressionStatement>() {
             self.eval(statement.expression.as_ref(), env)
         } else if let Some(integer_literal) = node.as_any().downcast_ref::<IntegerLiteral>() {
             Ok(Box::new(Integer::new(integer_literal.value)))
+        } else if let Some(float_literal) = node.as_any().downcast_ref::<FloatLiteral>() {
+            Ok(Box::new(Float::new(float_literal.value)))
         } else if let Some(boolean_literal) = node.as_any().downcast_ref::<BooleanLiteral>() {
             Ok(Box::new(Boolean::new(boolean_literal.value)))
         } else if node.as_any().is::<NullLiteral>() {
             Ok(Box::new(Null::new()))
         } else if let Some(function_literal) = node.as_any().downcast_ref::<FunctionLiteral>() {
-            let function_env = Environment::new_wrapped(env);
+            // Cycles can only form once closures are created, so this is where they're freed
+            environment::collect_cycles_if_due();
             Ok(Box::new(Function::new(
                 &function_literal.parameters,
                 function_literal.body.clone(),
-                function_env,
+                env.clone(),
             )))
         } else if let Some(identifier) = node.as_any().downcast_ref::<Identifier>() {
             match env.get(&identifier.value) {
-                Some(value) => Ok(value.clone()),
+                Some(value) => Ok(value),
                 None => match self.builtin_fns.get(&identifier.value) {
-                    Some(value) => Ok(value.clone()),
-                    None => Err(EvaluatorError::new(&format!(
-                        "Unknown identifier found: {}",
-                        identifier.value
-                    ))),
+                    Some(builtin_fn) => Ok(Box::new(builtin_fn.clone())),
+                    None => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownIdentifier {
+                        name: identifier.value.clone(),
+                    })),
                 },
             }
         } else if let Some(index_expression) = node.as_any().downcast_ref::<IndexExpression>() {
             self.eval_index_expression(index_expression, env)
+        } else if let Some(assign_expression) = node.as_any().downcast_ref::<AssignExpression>() {
+            self.eval_assign_expression(assign_expression, env)
+        } else if let Some(slice_expression) = node.as_any().downcast_ref::<SliceExpression>() {
+            self.eval_slice_expression(slice_expression, env)
         } else if let Some(call_expression) = node.as_any().downcast_ref::<CallExpression>() {
-            self.eval_call_expression(call_expression, env)
+            self.eval_call_expression(call_expression, env, false)
         } else if let Some(prefix_expression) = node.as_any().downcast_ref::<PrefixExpression>() {
             self.eval_prefix_expression(prefix_expression, env)
         } else if let Some(infix_expression) = node.as_any().downcast_ref::<InfixExpression>() {
             self.eval_infix_expression(infix_expression, env)
         } else if let Some(if_expression) = node.as_any().downcast_ref::<IfExpression>() {
-            self.eval_if_expression(if_expression, env)
+            self.eval_if_expression(if_expression, env, false)
         } else if let Some(array_expression) = node.as_any().downcast_ref::<ArrayExpression>() {
             self.eval_array_expression(array_expression, env)
+        } else if let Some(hash_expression) = node.as_any().downcast_ref::<HashExpression>() {
+            self.eval_hash_expression(hash_expression, env)
         } else if let Some(string_expression) = node.as_any().downcast_ref::<StringExpression>() {
             Ok(Box::new(StringObject::new(string_expression.value.clone())))
+        } else if let Some(interpolated_string) =
+            node.as_any().downcast_ref::<InterpolatedStringExpression>()
+        {
+            self.eval_interpolated_string_expression(interpolated_string, env)
         } else if let Some(block_statement) = node.as_any().downcast_ref::<BlockStatement>() {
             let mut wrapped_env = Environment::new_wrapped(env);
-            self.eval_block_statement(&block_statement.statements, &mut wrapped_env, false)
+            self.eval_block_statement(&block_statement.statements, &mut wrapped_env, false, false)
         } else if let Some(return_statement) = node.as_any().downcast_ref::<ReturnStatement>() {
-            self.eval_return_statement(return_statement, env)
+            self.eval_return_statement(return_statement, env, false)
         } else if let Some(let_statement) = node.as_any().downcast_ref::<LetStatement>() {
             self.eval_let_statement(let_statement, env)
+        } else if let Some(while_statement) = node.as_any().downcast_ref::<WhileStatement>() {
+            self.eval_while_statement(while_statement, env)
+        } else if let Some(for_statement) = node.as_any().downcast_ref::<ForStatement>() {
+            self.eval_for_statement(for_statement, env)
+        } else if node.as_any().is::<BreakStatement>() {
+            Ok(Box::new(Break {}))
+        } else if node.as_any().is::<ContinueStatement>() {
+            Ok(Box::new(Continue {}))
         } else {
-            Err(EvaluatorError::new(
-                "Evaluator encountered unknown AST type",
-            ))
+            Err(EvaluatorError::new(EvaluatorErrorKind::UnknownNode))
         }
     }
 
+    // Evaluates `node` in tail position, ie. as the last thing the function it's in does. Calls to
+    // Donkey functions in tail position evaluate to a `TailCall` instead of being made, and
+    // `apply_function` makes them once the calling function has returned.
+    fn eval_tail<T: Node + ?Sized>(
+        &mut self,
+        node: &T,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        self.step()
+            .and_then(|()| self.eval_tail_node(node, env))
+            .map_err(|error| error.or_span(node.span()))
+    }
+
+    fn eval_tail_node<T: Node + ?Sized>(
+        &mut self,
+        node: &T,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        if let Some(statement) = node.as_any().downcast_ref::<ExpressionStatement>() {
+            self.eval_tail(statement.expression.as_ref(), env)
+        } else if let Some(call_expression) = node.as_any().downcast_ref::<CallExpression>() {
+            self.eval_call_expression(call_expression, env, true)
+        } else if let Some(if_expression) = node.as_any().downcast_ref::<IfExpression>() {
+            self.eval_if_expression(if_expression, env, true)
+        } else if let Some(block_statement) = node.as_any().downcast_ref::<BlockStatement>() {
+            let mut wrapped_env = Environment::new_wrapped(env);
+            self.eval_block_statement(&block_statement.statements, &mut wrapped_env, false, true)
+        } else if let Some(return_statement) = node.as_any().downcast_ref::<ReturnStatement>() {
+            self.eval_return_statement(return_statement, env, true)
+        } else {
+            self.eval_node(node, env)
+        }
+    }
+
+    // If `tail_position` is set, the last statement is evaluated in tail position
     fn eval_block_statement(
         &mut self,
         statements: &[Box<dyn Statement>],
         env: &mut Environment,
         unwrap_return_value: bool,
+        tail_position: bool,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let mut ret: Box<dyn Object> = Box::new(Integer::new(69));
-        for statement in statements {
-            ret = self.eval(statement.as_ref(), env)?;
+        let mut ret: Box<dyn Object> = Box::new(Null::new());
+        for (i, statement) in statements.iter().enumerate() {
+            ret = if tail_position && i + 1 == statements.len() {
+                self.eval_tail(statement.as_ref(), env)?
+            } else {
+                self.eval(statement.as_ref(), env)?
+            };
             if ret.as_any().is::<ReturnValue>() {
                 if unwrap_return_value {
                     let ret: Box<dyn Any> = ret;
@@ -206,6 +388,9 @@ impl Evaluator {
                 } else {
                     return Ok(ret);
                 }
+            } else if ret.as_any().is::<Break>() || ret.as_any().is::<Continue>() {
+                // The parser makes sure these are inside a loop, which handles them
+                return Ok(ret);
             }
         }
         Ok(ret)
@@ -216,130 +401,279 @@ impl Evaluator {
         index_expression: &IndexExpression,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let index = self.eval(index_expression.index.as_ref(), env)?;
-        if let Some(index) = index.as_any().downcast_ref::<Integer>() {
-            let collection = self.eval(index_expression.collection.as_ref(), env)?;
-            if let Some(collection) = collection.as_any().downcast_ref::<Array>() {
-                let index = index.value as usize;
-                if index >= collection.items.len() {
-                    Err(EvaluatorError::new(&format!(
-                        "Out of bounds array access. Index is {} but array length is {}",
-                        index,
-                        collection.items.len()
-                    )))
-                } else {
-                    Ok(collection.items[index].clone())
-                }
-            } else {
-                Err(EvaluatorError::new(
-                    "Expected collection to be an array when the index is an integer literal",
-                ))
+        let collection = eval_value!(self, index_expression.collection.as_ref(), env);
+        let index = eval_value!(self, index_expression.index.as_ref(), env);
+        get_item(collection.as_ref(), index.as_ref(), index_expression)
+    }
+
+    // Evaluates an assignment and returns the assigned value. Arrays and hashes are values, so
+    // assigning to an item of one builds an updated copy of it and assigns that to the variable
+    // it came from. Other variables holding the old array or hash don't see the change.
+    fn eval_assign_expression(
+        &mut self,
+        assign_expression: &AssignExpression,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        // Split a target like `a[i][j]` into the variable `a` and the index expressions `a[i]`
+        // and `a[i][j]`
+        let mut index_expressions = Vec::new();
+        let mut target = assign_expression.target.as_ref();
+        while let Some(index_expression) = target.as_any().downcast_ref::<IndexExpression>() {
+            index_expressions.push(index_expression);
+            target = index_expression.collection.as_ref();
+        }
+        index_expressions.reverse();
+        let name = target
+            .as_any()
+            .downcast_ref::<Identifier>()
+            .expect("Assignment target should be an identifier or an index expression");
+        // The compound operator without the `=`, eg. `+` for `+=`
+        let operator = assign_expression.operator.trim_end_matches('=');
+
+        if index_expressions.is_empty() && operator.is_empty() {
+            let value = eval_value!(self, assign_expression.value.as_ref(), env);
+            let value = name_function(value, &name.value);
+            assign_variable(env, name, value.clone())?;
+            return Ok(value);
+        }
+
+        // The collections the target is in, from the value of the variable to the innermost
+        // collection, along with the index of the target in each of them
+        let Some(variable) = env.get(&name.value) else {
+            return Err(
+                EvaluatorError::new(EvaluatorErrorKind::UndeclaredAssignment {
+                    name: name.value.clone(),
+                })
+                .or_span(name.span),
+            );
+        };
+        let mut collections = vec![variable];
+        let mut indexes = Vec::new();
+        for (i, index_expression) in index_expressions.iter().enumerate() {
+            let index = eval_value!(self, index_expression.index.as_ref(), env);
+            if i + 1 < index_expressions.len() {
+                let collection = collections.last().expect("There is always a collection");
+                collections.push(get_item(
+                    collection.as_ref(),
+                    index.as_ref(),
+                    index_expression,
+                )?);
             }
+            indexes.push(index);
+        }
+
+        let mut value = eval_value!(self, assign_expression.value.as_ref(), env);
+        if !operator.is_empty() {
+            let current = match (indexes.last(), index_expressions.last()) {
+                (Some(index), Some(index_expression)) => get_item(
+                    collections
+                        .last()
+                        .expect("There is always a collection")
+                        .as_ref(),
+                    index.as_ref(),
+                    index_expression,
+                )?,
+                _ => collections[0].clone(),
+            };
+            value = self.apply_numeric_operator(
+                operator,
+                current.as_ref(),
+                value.as_ref(),
+                (
+                    assign_expression.target.span(),
+                    assign_expression.value.span(),
+                ),
+            )?;
+        }
+
+        // Put the value into each collection, from the innermost one out
+        let mut updated = value.clone();
+        for ((collection, index), index_expression) in collections
+            .iter()
+            .zip(&indexes)
+            .zip(&index_expressions)
+            .rev()
+        {
+            updated = set_item(
+                collection.as_ref(),
+                index.as_ref(),
+                updated,
+                index_expression,
+            )?;
+        }
+        assign_variable(env, name, updated)?;
+        Ok(value)
+    }
+
+    fn eval_slice_expression(
+        &mut self,
+        slice_expression: &SliceExpression,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let collection = eval_value!(self, slice_expression.collection.as_ref(), env);
+        let mut bounds = Vec::new();
+        for expression in [&slice_expression.start, &slice_expression.end] {
+            bounds.push(match expression {
+                Some(expression) => Some(eval_value!(self, expression.as_ref(), env)),
+                None => None,
+            });
+        }
+        if let Some(array) = collection.as_any().downcast_ref::<Array>() {
+            let (start, end) = slice_bounds(slice_expression, &bounds, array.items.len())?;
+            Ok(Box::new(Array::new(array.items[start..end].to_vec())))
+        } else if let Some(string) = collection.as_any().downcast_ref::<StringObject>() {
+            // Like indexing, slicing a string counts characters
+            let len = string.value.chars().count();
+            let (start, end) = slice_bounds(slice_expression, &bounds, len)?;
+            let slice = string.value.chars().skip(start).take(end - start).collect();
+            Ok(Box::new(StringObject::new(slice)))
         } else {
-            Err(EvaluatorError::new(
-                "Expected index to be an integer literal",
-            ))
+            Err(type_error("array or string", collection.as_ref())
+                .or_span(slice_expression.collection.span()))
         }
     }
 
+    fn eval_hash_expression(
+        &mut self,
+        hash_expression: &HashExpression,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let mut pairs = BTreeMap::new();
+        for (key, value) in &hash_expression.pairs {
+            let key_object = eval_value!(self, key.as_ref(), env);
+            let hash_key =
+                hash_key(key_object.as_ref()).map_err(|error| error.or_span(key.span()))?;
+            let value = eval_value!(self, value.as_ref(), env);
+            pairs.insert(hash_key, value);
+        }
+        Ok(Box::new(Hash::new(pairs)))
+    }
+
     fn eval_call_expression(
         &mut self,
         call_expression: &CallExpression,
         env: &mut Environment,
+        tail_position: bool,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let function = eval_value!(self, call_expression.function.as_ref(), env);
+        if !(function.as_any().is::<Function>() || function.as_any().is::<BuiltinFn>()) {
+            return Err(EvaluatorError::new(EvaluatorErrorKind::NotCallable {
+                found: function.type_name(),
+            })
+            .or_span(call_expression.function.span()));
+        }
         let mut arguments = Vec::new();
         // Evaluate the call expression arguments. If any of them return an error, immediately
         // return from this function with that error.
         for arg in &call_expression.arguments {
-            match self.eval(arg.as_ref(), env) {
-                Ok(arg) => arguments.push(arg),
-                Err(e) => return Err(e),
-            }
+            arguments.push(eval_value!(self, arg.as_ref(), env));
         }
-        if let Some(function_literal) = call_expression
-            .function
-            .as_any()
-            .downcast_ref::<FunctionLiteral>()
-        {
-            let function: Box<dyn Any> = self.eval(function_literal, env)?;
-            if let Ok(function) = function.downcast::<Function>() {
-                self.apply_function(function, arguments)
-            } else {
-                Err(EvaluatorError::new(
-                    "Expected function literal to evaluate to function",
-                ))
-            }
-        } else if let Some(identifier) = call_expression
-            .function
-            .as_any()
-            .downcast_ref::<Identifier>()
+        // Builtins don't call back into Donkey code, so calling them right away can't grow the
+        // stack. Calls with the wrong number of arguments are also made right away, so that the
+        // error is reported while the calling function is still on the call stack.
+        if tail_position
+            && let Some(function) = function.as_any().downcast_ref::<Function>()
+            && function.parameters.len() == arguments.len()
         {
-            if let Some(value) = env.get(&identifier.value) {
-                let value: Box<dyn Any> = value.clone();
-                if value.is::<SelfRef>() {
-                    if let Some(self_fn) = self.self_fn.clone() {
-                        self.apply_function(self_fn, arguments)
-                    } else {
-                        Err(EvaluatorError::new(
-                            "Expected self_fn to be Some when evaluating recursive function",
-                        ))
-                    }
-                } else if let Ok(function) = value.downcast::<Function>() {
-                    let temp = self.self_fn.clone();
-                    self.self_fn = Some(function.clone());
-                    let ret = self.apply_function(function, arguments);
-                    self.self_fn = temp;
-                    ret
-                } else {
-                    Err(EvaluatorError::new(&format!(
-                        "Expected function literal in call expression. {} is not a function literal",
-                        identifier.value
-                    )))
-                }
-            } else if let Some(value) = self.builtin_fns.get(&identifier.value) {
-                // Check for builtin functions here
-                let value: Box<dyn Any> = value.clone();
-                if let Ok(builtin_fn) = value.downcast::<BuiltinFn>() {
-                    let builtin_fn = builtin_fn.builtin_fn;
-                    builtin_fn(arguments)
-                } else {
-                    Err(EvaluatorError::new(
-                        "Unable to downcast to builtin function",
-                    ))
-                }
-            } else {
-                Err(EvaluatorError::new(&format!(
-                    "Unknown identifier: {}",
-                    identifier.value
-                )))
+            return Ok(Box::new(TailCall::new(
+                function.clone(),
+                arguments,
+                call_expression.span,
+            )));
+        }
+        self.call_function(function.as_ref(), arguments, call_expression.span)
+    }
+
+    /// Calls `function` with `arguments` as if it was called from a call expression at `call_site`.
+    /// `function` can be a Donkey function or a builtin function.
+    pub fn call_function(
+        &mut self,
+        function: &dyn Object,
+        arguments: Vec<Box<dyn Object>>,
+        call_site: Span,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        if self.stack_base.is_none() {
+            return self.run_outermost(|evaluator| {
+                evaluator.call_function(function, arguments, call_site)
+            });
+        }
+        let _tracking = self.track_memory();
+        if let Some(function) = function.as_any().downcast_ref::<Function>() {
+            self.apply_function(function.clone(), arguments, call_site)
+        } else if let Some(builtin_fn) = function.as_any().downcast_ref::<BuiltinFn>() {
+            if !builtin_fn.arity.accepts(arguments.len()) {
+                return Err(EvaluatorError::new(EvaluatorErrorKind::ArityMismatch {
+                    function: Some(builtin_fn.name.clone()),
+                    expected: builtin_fn.arity,
+                    found: arguments.len(),
+                }));
             }
+            let result = (builtin_fn.builtin_fn)(Arguments::new(&builtin_fn.name, arguments))?;
+            // A builtin can allocate a lot in one call, so the limit is checked right away
+            self.memory_usage.check(0)?;
+            Ok(result)
         } else {
-            Err(EvaluatorError::new(
-                "Expected function literal or identifier in call expression",
-            ))
+            Err(EvaluatorError::new(EvaluatorErrorKind::NotCallable {
+                found: function.type_name(),
+            }))
         }
     }
 
     fn apply_function(
         &mut self,
-        mut function: Box<Function>,
-        arguments: Vec<Box<dyn Object>>,
+        mut function: Function,
+        mut arguments: Vec<Box<dyn Object>>,
+        mut call_site: Span,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        if function.parameters.len() != arguments.len() {
-            return Err(EvaluatorError::new(&format!(
-                "Mismatched number of parameters in call expression: {} != {}",
-                function.parameters.len(),
-                arguments.len()
-            )));
+        // A function that ends with a call returns it as a `TailCall`, which is made here in a
+        // loop instead of recursively. The frame of the function that made a tail call is gone by
+        // then, so it doesn't show up in backtraces.
+        loop {
+            if function.parameters.len() != arguments.len() {
+                return Err(EvaluatorError::new(EvaluatorErrorKind::ArityMismatch {
+                    function: function.name.clone(),
+                    expected: Arity::Exact(function.parameters.len()),
+                    found: arguments.len(),
+                }));
+            }
+            if self.call_stack.len() >= self.max_call_depth {
+                return Err(
+                    EvaluatorError::new(EvaluatorErrorKind::RecursionLimitExceeded {
+                        depth: self.call_stack.len(),
+                    })
+                    .or_backtrace(&self.call_stack),
+                );
+            }
+            // Each call gets its own scope for the parameters, so recursive calls don't overwrite
+            // each other's arguments
+            let mut call_env = Environment::new_wrapped(&function.env);
+            function
+                .parameters
+                .iter()
+                .zip(arguments)
+                .for_each(|(param, arg)| {
+                    call_env.insert(param, arg);
+                });
+            self.call_stack
+                .push(Frame::new(function.name.clone(), call_site));
+            let ret = self
+                .eval_block_statement(&function.body.statements, &mut call_env, true, true)
+                // The innermost call to fail records the full call stack, since the frames are
+                // popped as the error propagates outwards
+                .map_err(|error| error.or_backtrace(&self.call_stack));
+            self.call_stack.pop();
+            let ret = ret?;
+            if !ret.as_any().is::<TailCall>() {
+                return Ok(ret);
+            }
+            let ret: Box<dyn Any> = ret;
+            let tail_call = ret
+                .downcast::<TailCall>()
+                .expect("Object should be TailCall");
+            function = tail_call.function;
+            arguments = tail_call.arguments;
+            call_site = tail_call.call_site;
         }
-        function
-            .parameters
-            .iter()
-            .zip(arguments)
-            .for_each(|(param, arg)| {
-                function.env.insert(param, arg);
-            });
-        self.eval_block_statement(&function.body.statements, &mut function.env, true)
     }
 
     fn eval_prefix_expression(
@@ -350,7 +684,9 @@ impl Evaluator {
         match prefix_expression.operator.as_ref() {
             "!" => self.eval_bang_expression(prefix_expression.right.as_ref(), env),
             "-" => self.eval_minus_expression(prefix_expression.right.as_ref(), env),
-            _ => Err(EvaluatorError::new("Unknown operator in prefix expression")),
+            operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+                operator: operator.to_string(),
+            })),
         }
     }
 
@@ -360,48 +696,155 @@ impl Evaluator {
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
         match infix_expression.operator.as_ref() {
-            "+" | "-" | "*" | "/" | ">=" | "<=" | ">" | "<" => {
-                self.eval_integer_infix_expression(infix_expression, env)
+            "+" | "-" | "*" | "/" | "%" | ">=" | "<=" | ">" | "<" => {
+                self.eval_numeric_infix_expression(infix_expression, env)
             }
             "==" | "!=" => self.eval_equality_infix_expression(infix_expression, env),
-            _ => Err(EvaluatorError::new("Unknown operator in infix expression")),
+            "&&" | "||" => self.eval_logical_infix_expression(infix_expression, env),
+            operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+                operator: operator.to_string(),
+            })),
         }
     }
 
-    fn eval_integer_infix_expression(
+    fn eval_numeric_infix_expression(
         &mut self,
         infix_expression: &InfixExpression,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let left = self.eval(infix_expression.left.as_ref(), env)?;
-        let right = self.eval(infix_expression.right.as_ref(), env)?;
-        if let Some(left) = left.as_any().downcast_ref::<Integer>()
+        let left = eval_value!(self, infix_expression.left.as_ref(), env);
+        let right = eval_value!(self, infix_expression.right.as_ref(), env);
+        self.apply_numeric_operator(
+            &infix_expression.operator,
+            left.as_ref(),
+            right.as_ref(),
+            (infix_expression.left.span(), infix_expression.right.span()),
+        )
+    }
+
+    // Applies an arithmetic or comparison operator to evaluated operands. `spans` are the spans of
+    // the left and right operands, which type errors point at.
+    fn apply_numeric_operator(
+        &self,
+        operator: &str,
+        left: &dyn Object,
+        right: &dyn Object,
+        (left_span, right_span): (Span, Span),
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        if let Some(left) = left.as_any().downcast_ref::<StringObject>()
+            && matches!(operator, "+" | ">" | "<" | ">=" | "<=")
+        {
+            let Some(right) = right.as_any().downcast_ref::<StringObject>() else {
+                return Err(type_error("string", right).or_span(right_span));
+            };
+            eval_string_infix_expression(operator, &left.value, &right.value)
+        } else if let Some(left) = left.as_any().downcast_ref::<Integer>()
             && let Some(right) = right.as_any().downcast_ref::<Integer>()
         {
-            match infix_expression.operator.as_ref() {
-                "+" => Ok(Box::new(Integer::new(left.value + right.value))),
-                "-" => Ok(Box::new(Integer::new(left.value - right.value))),
-                "*" => Ok(Box::new(Integer::new(left.value * right.value))),
+            let value = match operator {
+                "+" => left.value.checked_add(right.value),
+                "-" => left.value.checked_sub(right.value),
+                "*" => left.value.checked_mul(right.value),
                 "/" => {
                     if right.value == 0 {
-                        Err(EvaluatorError::new("Division by zero"))
+                        return Err(EvaluatorError::new(EvaluatorErrorKind::DivisionByZero));
+                    }
+                    // This only overflows for i64::MIN / -1
+                    left.value.checked_div(right.value)
+                }
+                "%" => {
+                    if right.value == 0 {
+                        return Err(EvaluatorError::new(EvaluatorErrorKind::ModuloByZero));
+                    }
+                    // i64::MIN % -1 overflows when computing the quotient, but the remainder is 0
+                    Some(left.value.wrapping_rem(right.value))
+                }
+                ">" => return Ok(Box::new(Boolean::new(left.value > right.value))),
+                "<" => return Ok(Box::new(Boolean::new(left.value < right.value))),
+                ">=" => return Ok(Box::new(Boolean::new(left.value >= right.value))),
+                "<=" => return Ok(Box::new(Boolean::new(left.value <= right.value))),
+                operator => {
+                    return Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+                        operator: operator.to_string(),
+                    }));
+                }
+            };
+            match (value, self.overflow_behavior) {
+                (Some(value), _) => Ok(Box::new(Integer::new(value))),
+                (None, OverflowBehavior::Error) => {
+                    Err(EvaluatorError::new(EvaluatorErrorKind::IntegerOverflow {
+                        operation: format!("{} {operator} {}", left.value, right.value),
+                    }))
+                }
+                (None, OverflowBehavior::Promote) => eval_big_integer_infix_expression(
+                    operator,
+                    &BigInt::from(left.value),
+                    &BigInt::from(right.value),
+                ),
+            }
+        } else if let (Some(left), Some(right)) =
+            (big_integer_value(left), big_integer_value(right))
+        {
+            // At least one of the operands is a big integer, which only exist when overflowing
+            // results are promoted
+            eval_big_integer_infix_expression(operator, &left, &right)
+        } else if let (Some(left), Some(right)) = (float_value(left), float_value(right)) {
+            // At least one of the operands is a float, so the other one is converted to a float
+            match operator {
+                "+" => Ok(Box::new(Float::new(left + right))),
+                "-" => Ok(Box::new(Float::new(left - right))),
+                "*" => Ok(Box::new(Float::new(left * right))),
+                "/" => {
+                    if right == 0.0 {
+                        Err(EvaluatorError::new(EvaluatorErrorKind::DivisionByZero))
+                    } else {
+                        Ok(Box::new(Float::new(left / right)))
+                    }
+                }
+                "%" => {
+                    if right == 0.0 {
+                        Err(EvaluatorError::new(EvaluatorErrorKind::ModuloByZero))
                     } else {
-                        Ok(Box::new(Integer::new(left.value / right.value)))
+                        Ok(Box::new(Float::new(left % right)))
                     }
                 }
-                ">" => Ok(Box::new(Boolean::new(left.value > right.value))),
-                "<" => Ok(Box::new(Boolean::new(left.value < right.value))),
-                ">=" => Ok(Box::new(Boolean::new(left.value >= right.value))),
-                "<=" => Ok(Box::new(Boolean::new(left.value <= right.value))),
-                _ => Err(EvaluatorError::new("Unknown integer infix operator")),
+                ">" => Ok(Box::new(Boolean::new(left > right))),
+                "<" => Ok(Box::new(Boolean::new(left < right))),
+                ">=" => Ok(Box::new(Boolean::new(left >= right))),
+                "<=" => Ok(Box::new(Boolean::new(left <= right))),
+                operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+                    operator: operator.to_string(),
+                })),
             }
+        } else if float_value(left).is_some() {
+            Err(type_error(left.type_name(), right).or_span(right_span))
         } else {
-            Err(EvaluatorError::new(
-                "Expected integer expressions in infix expression",
-            ))
+            let expected = if float_value(right).is_some() {
+                right.type_name()
+            } else {
+                "integer"
+            };
+            Err(type_error(expected, left).or_span(left_span))
         }
     }
 
+    // `&&` and `||` only evaluate their right operand if the left operand doesn't already decide
+    // the result. Like `if`, they accept operands of any type and check whether they are truthy,
+    // and the result is always a boolean.
+    fn eval_logical_infix_expression(
+        &mut self,
+        infix_expression: &InfixExpression,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let left = eval_value!(self, infix_expression.left.as_ref(), env);
+        let short_circuit_value = infix_expression.operator == "||";
+        if is_truthy(left.as_ref()) == short_circuit_value {
+            return Ok(Box::new(Boolean::new(short_circuit_value)));
+        }
+        let right = eval_value!(self, infix_expression.right.as_ref(), env);
+        Ok(Box::new(Boolean::new(is_truthy(right.as_ref()))))
+    }
+
     // Note: It is valid in the Monkey language to compare two expressions of different types. Two expressions of different types are
     // always considered to be not equal.
     fn eval_equality_infix_expression(
@@ -409,53 +852,84 @@ impl Evaluator {
         infix_expression: &InfixExpression,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let left = self.eval(infix_expression.left.as_ref(), env)?;
-        let right = self.eval(infix_expression.right.as_ref(), env)?;
+        let left = eval_value!(self, infix_expression.left.as_ref(), env);
+        let right = eval_value!(self, infix_expression.right.as_ref(), env);
         match infix_expression.operator.as_ref() {
-            "==" => {
-                if let (Some(left_int), Some(right_int)) = (
-                    left.as_any().downcast_ref::<Integer>(),
-                    right.as_any().downcast_ref::<Integer>(),
-                ) {
-                    Ok(Box::new(Boolean::new(left_int.value == right_int.value)))
-                } else if let (Some(left_bool), Some(right_bool)) = (
-                    left.as_any().downcast_ref::<Boolean>(),
-                    right.as_any().downcast_ref::<Boolean>(),
-                ) {
-                    Ok(Box::new(Boolean::new(left_bool.value == right_bool.value)))
-                } else if left.as_any().is::<Null>() && right.as_any().is::<Null>() {
-                    Ok(Box::new(Boolean::new(true)))
-                } else {
-                    Ok(Box::new(Boolean::new(false)))
-                }
+            "==" => Ok(Box::new(Boolean::new(left.equals(right.as_ref())))),
+            "!=" => Ok(Box::new(Boolean::new(!left.equals(right.as_ref())))),
+            operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+                operator: operator.to_string(),
+            })),
+        }
+    }
+
+    fn eval_while_statement(
+        &mut self,
+        while_statement: &WhileStatement,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        loop {
+            let condition = eval_value!(self, while_statement.condition.as_ref(), env);
+            if !is_truthy(condition.as_ref()) {
+                break;
             }
-            "!=" => {
-                if let (Some(left_int), Some(right_int)) = (
-                    left.as_any().downcast_ref::<Integer>(),
-                    right.as_any().downcast_ref::<Integer>(),
-                ) {
-                    Ok(Box::new(Boolean::new(left_int.value != right_int.value)))
-                } else if let (Some(left_bool), Some(right_bool)) = (
-                    left.as_any().downcast_ref::<Boolean>(),
-                    right.as_any().downcast_ref::<Boolean>(),
-                ) {
-                    Ok(Box::new(Boolean::new(left_bool.value != right_bool.value)))
-                } else if left.as_any().is::<Null>() && right.as_any().is::<Null>() {
-                    Ok(Box::new(Boolean::new(false)))
-                } else {
-                    Ok(Box::new(Boolean::new(true)))
-                }
+            let ret = self.eval(&while_statement.body, env)?;
+            if ret.as_any().is::<Break>() {
+                break;
+            } else if ret.as_any().is::<ReturnValue>() {
+                return Ok(ret);
             }
-            _ => Err(EvaluatorError::new("Unknown boolean infix operator")),
         }
+        Ok(Box::new(Null::new()))
+    }
+
+    fn eval_for_statement(
+        &mut self,
+        for_statement: &ForStatement,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let collection = eval_value!(self, for_statement.collection.as_ref(), env);
+        let items = loop_items(collection.as_ref())
+            .map_err(|error| error.or_span(for_statement.collection.span()))?;
+        for item in items {
+            // The body can be empty, so iterations are counted on their own
+            self.step()
+                .map_err(|error| error.or_span(for_statement.collection.span()))?;
+            // Each iteration gets its own scope, so closures created in the body capture the item
+            // of their own iteration
+            let mut loop_env = Environment::new_wrapped(env);
+            loop_env.insert(&for_statement.variable, item);
+            let ret = self.eval_block_statement(
+                &for_statement.body.statements,
+                &mut loop_env,
+                false,
+                false,
+            )?;
+            if ret.as_any().is::<Break>() {
+                break;
+            } else if ret.as_any().is::<ReturnValue>() {
+                return Ok(ret);
+            }
+        }
+        Ok(Box::new(Null::new()))
     }
 
     fn eval_return_statement(
         &mut self,
         return_statement: &ReturnStatement,
         env: &mut Environment,
+        tail_position: bool,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let expression = self.eval(return_statement.return_value.as_ref(), env)?;
+        // The value is only in tail position if the statement is. A return statement elsewhere,
+        // like in a loop or in an expression, doesn't necessarily end the function it's in.
+        let expression = if tail_position {
+            self.eval_tail(return_statement.return_value.as_ref(), env)?
+        } else {
+            self.eval(return_statement.return_value.as_ref(), env)?
+        };
+        if is_signal(expression.as_ref()) {
+            return Ok(expression);
+        }
         Ok(Box::new(ReturnValue::new(expression)))
     }
 
@@ -464,37 +938,76 @@ impl Evaluator {
         let_statement: &LetStatement,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        if let_statement.rec {
-            env.insert(&let_statement.name, Box::new(SelfRef::new()));
-            let value = self.eval(let_statement.value.as_ref(), env)?;
-            env.insert(&let_statement.name, value);
-            Ok(Box::new(Null::new()))
-        } else {
-            let value = self.eval(let_statement.value.as_ref(), env)?;
-            env.insert(&let_statement.name, value);
-            Ok(Box::new(Null::new()))
+        if !let_statement.rec {
+            let value = eval_value!(self, let_statement.value.as_ref(), env);
+            let value = name_function(value, &let_statement.name.value);
+            if let_statement.mutable {
+                env.insert_mutable(&let_statement.name, value);
+            } else {
+                env.insert(&let_statement.name, value);
+            }
+            return Ok(Box::new(Null::new()));
+        }
+        // The functions of a let rec statement are defined in a scope of their own that binds all
+        // of them, so they can call each other no matter where they are called from or how their
+        // names are rebound later
+        let mut rec_env = Environment::new_wrapped(env);
+        let mut functions = Vec::new();
+        for (name, value) in let_statement.bindings() {
+            let function = eval_value!(self, value, &mut rec_env);
+            functions.push((name, name_function(function, &name.value)));
         }
+        for (name, function) in functions {
+            rec_env.insert(name, function.clone());
+            env.insert(name, function);
+        }
+        Ok(Box::new(Null::new()))
     }
 
+    // If `tail_position` is set, the branch that is taken is evaluated in tail position
     fn eval_if_expression(
         &mut self,
         if_expression: &IfExpression,
         env: &mut Environment,
+        tail_position: bool,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let mut branch = if_expression.alternative.as_ref();
         for cons in &if_expression.consequences {
-            let condition = self.eval(cons.0.as_ref(), env)?;
+            let condition = eval_value!(self, cons.0.as_ref(), env);
             if is_truthy(condition.as_ref()) {
-                return self.eval(&cons.1, env);
+                branch = Some(&cons.1);
+                break;
             }
         }
-        if let Some(alternative) = if_expression.alternative.as_ref() {
-            self.eval(alternative, env)
+        if let Some(branch) = branch {
+            if tail_position {
+                self.eval_tail(branch, env)
+            } else {
+                self.eval(branch, env)
+            }
         } else {
             // If the if_expression has no else branch and all the conditions are falsey, then it evaluates to null
             Ok(Box::new(Null::new()))
         }
     }
 
+    fn eval_interpolated_string_expression(
+        &mut self,
+        interpolated_string: &InterpolatedStringExpression,
+        env: &mut Environment,
+    ) -> Result<Box<dyn Object>, EvaluatorError> {
+        let mut value = interpolated_string.strings[0].clone();
+        for (expression, string) in interpolated_string
+            .expressions
+            .iter()
+            .zip(&interpolated_string.strings[1..])
+        {
+            value.push_str(&eval_value!(self, expression.as_ref(), env).display());
+            value.push_str(string);
+        }
+        Ok(Box::new(StringObject::new(value)))
+    }
+
     fn eval_array_expression(
         &mut self,
         array_expression: &ArrayExpression,
@@ -502,7 +1015,7 @@ impl Evaluator {
     ) -> Result<Box<dyn Object>, EvaluatorError> {
         let mut items = Vec::new();
         for item in &array_expression.items {
-            let item_object = self.eval(item.as_ref(), env)?;
+            let item_object = eval_value!(self, item.as_ref(), env);
             items.push(item_object);
         }
         Ok(Box::new(Array::new(items)))
@@ -513,13 +1026,12 @@ impl Evaluator {
         right: &dyn Expression,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let right = self.eval(right, env)?;
+        let span = right.span();
+        let right = eval_value!(self, right, env);
         if let Some(boolean) = right.as_any().downcast_ref::<Boolean>() {
             Ok(Box::new(Boolean::new(!boolean.value)))
         } else {
-            Err(EvaluatorError::new(
-                "Expected boolean expression after bang operator",
-            ))
+            Err(type_error("boolean", right.as_ref()).or_span(span))
         }
     }
 
@@ -528,23 +1040,308 @@ impl Evaluator {
         right: &dyn Expression,
         env: &mut Environment,
     ) -> Result<Box<dyn Object>, EvaluatorError> {
-        let right = self.eval(right, env)?;
+        let span = right.span();
+        let right = eval_value!(self, right, env);
         if let Some(integer) = right.as_any().downcast_ref::<Integer>() {
-            Ok(Box::new(Integer::new(-integer.value)))
+            match (integer.value.checked_neg(), self.overflow_behavior) {
+                (Some(value), _) => Ok(Box::new(Integer::new(value))),
+                (None, OverflowBehavior::Error) => {
+                    Err(EvaluatorError::new(EvaluatorErrorKind::IntegerOverflow {
+                        operation: format!("-{}", integer.value),
+                    }))
+                }
+                (None, OverflowBehavior::Promote) => {
+                    Ok(BigInteger::normalized(-&BigInt::from(integer.value)))
+                }
+            }
+        } else if let Some(big_integer) = right.as_any().downcast_ref::<BigInteger>() {
+            Ok(BigInteger::normalized(-&big_integer.value))
+        } else if let Some(float) = right.as_any().downcast_ref::<Float>() {
+            Ok(Box::new(Float::new(-float.value)))
         } else {
-            Err(EvaluatorError::new(
-                "Expected integer expression after minus operator",
-            ))
+            Err(type_error("integer", right.as_ref()).or_span(span))
         }
     }
 }
 
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 fn is_truthy(expression: &dyn Object) -> bool {
     if let Some(boolean) = expression.as_any().downcast_ref::<Boolean>() {
         boolean.value
     } else if let Some(integer) = expression.as_any().downcast_ref::<Integer>() {
         integer.value != 0
+    } else if let Some(big_integer) = expression.as_any().downcast_ref::<BigInteger>() {
+        !big_integer.value.is_zero()
+    } else if let Some(float) = expression.as_any().downcast_ref::<Float>() {
+        float.value != 0.0
     } else {
         false
     }
 }
+
+// Whether `object` is the signal of a `return`, `break` or `continue`, which is passed up to the
+// function or loop it ends rather than used as a value
+fn is_signal(object: &dyn Object) -> bool {
+    object.as_any().is::<ReturnValue>()
+        || object.as_any().is::<Break>()
+        || object.as_any().is::<Continue>()
+}
+
+// Returns the value of `object` as a float if it is a number, converting integers to floats.
+fn float_value(object: &dyn Object) -> Option<f64> {
+    if let Some(float) = object.as_any().downcast_ref::<Float>() {
+        Some(float.value)
+    } else {
+        big_integer_value(object).map(|integer| integer.to_f64())
+    }
+}
+
+// Returns the value of `object` as a big integer if it is an integer of either size.
+fn big_integer_value(object: &dyn Object) -> Option<BigInt> {
+    if let Some(integer) = object.as_any().downcast_ref::<Integer>() {
+        Some(BigInt::from(integer.value))
+    } else {
+        object
+            .as_any()
+            .downcast_ref::<BigInteger>()
+            .map(|big_integer| big_integer.value.clone())
+    }
+}
+
+// Evaluates concatenation and lexicographic comparison of strings
+fn eval_string_infix_expression(
+    operator: &str,
+    left: &str,
+    right: &str,
+) -> Result<Box<dyn Object>, EvaluatorError> {
+    match operator {
+        "+" => {
+            memory::reserve(left.len() + right.len())?;
+            Ok(Box::new(StringObject::new(format!("{left}{right}"))))
+        }
+        ">" => Ok(Box::new(Boolean::new(left > right))),
+        "<" => Ok(Box::new(Boolean::new(left < right))),
+        ">=" => Ok(Box::new(Boolean::new(left >= right))),
+        "<=" => Ok(Box::new(Boolean::new(left <= right))),
+        operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+            operator: operator.to_string(),
+        })),
+    }
+}
+
+// Evaluates integer arithmetic exactly, returning an `Integer` if the result fits in one
+fn eval_big_integer_infix_expression(
+    operator: &str,
+    left: &BigInt,
+    right: &BigInt,
+) -> Result<Box<dyn Object>, EvaluatorError> {
+    match operator {
+        "+" => Ok(BigInteger::normalized(left + right)),
+        "-" => Ok(BigInteger::normalized(left - right)),
+        "*" => Ok(BigInteger::normalized(left * right)),
+        "/" => match left.checked_div(right) {
+            Some(quotient) => Ok(BigInteger::normalized(quotient)),
+            None => Err(EvaluatorError::new(EvaluatorErrorKind::DivisionByZero)),
+        },
+        "%" => match left.checked_div(right) {
+            Some(quotient) => Ok(BigInteger::normalized(left - &(&quotient * right))),
+            None => Err(EvaluatorError::new(EvaluatorErrorKind::ModuloByZero)),
+        },
+        ">" => Ok(Box::new(Boolean::new(left > right))),
+        "<" => Ok(Box::new(Boolean::new(left < right))),
+        ">=" => Ok(Box::new(Boolean::new(left >= right))),
+        "<=" => Ok(Box::new(Boolean::new(left <= right))),
+        operator => Err(EvaluatorError::new(EvaluatorErrorKind::UnknownOperator {
+            operator: operator.to_string(),
+        })),
+    }
+}
+
+// Assigns `value` to the variable `name`, reporting why if it can't be assigned to.
+fn assign_variable(
+    env: &mut Environment,
+    name: &Identifier,
+    value: Box<dyn Object>,
+) -> Result<(), EvaluatorError> {
+    env.assign(&name.value, value).map_err(|error| {
+        let kind = match error {
+            AssignError::Undeclared => EvaluatorErrorKind::UndeclaredAssignment {
+                name: name.value.clone(),
+            },
+            AssignError::Immutable { declared_at } => EvaluatorErrorKind::ImmutableAssignment {
+                name: name.value.clone(),
+                declared_at,
+            },
+        };
+        EvaluatorError::new(kind).or_span(name.span)
+    })
+}
+
+// Returns the item of `collection` at `index`, for the index expression `index_expression`.
+fn get_item(
+    collection: &dyn Object,
+    index: &dyn Object,
+    index_expression: &IndexExpression,
+) -> Result<Box<dyn Object>, EvaluatorError> {
+    if let Some(collection) = collection.as_any().downcast_ref::<Array>() {
+        let index = sequence_index(index, collection.items.len())
+            .map_err(|error| error.or_span(index_expression.index.span()))?;
+        Ok(collection.items[index].clone())
+    } else if let Some(collection) = collection.as_any().downcast_ref::<StringObject>() {
+        // Strings are indexed by character, and indexing one gives a string of one character
+        let len = collection.value.chars().count();
+        let index = sequence_index(index, len)
+            .map_err(|error| error.or_span(index_expression.index.span()))?;
+        let c = collection.value.chars().nth(index).unwrap_or_default();
+        Ok(Box::new(StringObject::new(c.to_string())))
+    } else if let Some(collection) = collection.as_any().downcast_ref::<Hash>() {
+        let key = hash_key(index).map_err(|error| error.or_span(index_expression.index.span()))?;
+        match collection.pairs.get(&key) {
+            Some(value) => Ok(value.clone()),
+            None => Err(EvaluatorError::new(EvaluatorErrorKind::KeyNotFound {
+                key: index.inspect(),
+            })),
+        }
+    } else {
+        Err(type_error("array, string or hash", collection)
+            .or_span(index_expression.collection.span()))
+    }
+}
+
+// Returns a copy of `collection` with the item at `index` set to `value`, for an assignment to
+// `index_expression`. Only arrays and hashes can be assigned to, and arrays only at existing
+// indexes.
+fn set_item(
+    collection: &dyn Object,
+    index: &dyn Object,
+    value: Box<dyn Object>,
+    index_expression: &IndexExpression,
+) -> Result<Box<dyn Object>, EvaluatorError> {
+    if let Some(array) = collection.as_any().downcast_ref::<Array>() {
+        let index = sequence_index(index, array.items.len())
+            .map_err(|error| error.or_span(index_expression.index.span()))?;
+        let mut items = array.items.clone();
+        items[index] = value;
+        Ok(Box::new(Array::new(items)))
+    } else if let Some(hash) = collection.as_any().downcast_ref::<Hash>() {
+        let key = hash_key(index).map_err(|error| error.or_span(index_expression.index.span()))?;
+        let mut pairs = hash.pairs.clone();
+        pairs.insert(key, value);
+        Ok(Box::new(Hash::new(pairs)))
+    } else {
+        Err(type_error("array or hash", collection).or_span(index_expression.collection.span()))
+    }
+}
+
+// Returns the items a for loop over `collection` goes through: the items of an array, the
+// characters of a string as strings, the keys of a hash or the integers in a range.
+fn loop_items(
+    collection: &dyn Object,
+) -> Result<Box<dyn Iterator<Item = Box<dyn Object>>>, EvaluatorError> {
+    if let Some(array) = collection.as_any().downcast_ref::<Array>() {
+        Ok(Box::new(array.items.clone().into_iter()))
+    } else if let Some(string) = collection.as_any().downcast_ref::<StringObject>() {
+        let chars: Vec<char> = string.value.chars().collect();
+        Ok(Box::new(chars.into_iter().map(|c| {
+            Box::new(StringObject::new(c.to_string())) as Box<dyn Object>
+        })))
+    } else if let Some(hash) = collection.as_any().downcast_ref::<Hash>() {
+        let keys: Vec<_> = hash.pairs.keys().map(HashKey::to_object).collect();
+        Ok(Box::new(keys.into_iter()))
+    } else if let Some(range) = collection.as_any().downcast_ref::<Range>() {
+        Ok(Box::new((range.start..range.end).map(|value| {
+            Box::new(Integer::new(value)) as Box<dyn Object>
+        })))
+    } else {
+        Err(type_error("array, string, hash or range", collection))
+    }
+}
+
+// Checks that `index` is an integer that can index a sequence of length `len`.
+fn sequence_index(index: &dyn Object, len: usize) -> Result<usize, EvaluatorError> {
+    let Some(index) = index.as_any().downcast_ref::<Integer>() else {
+        return Err(type_error("integer", index));
+    };
+    if index.value < 0 || index.value as usize >= len {
+        Err(EvaluatorError::new(EvaluatorErrorKind::IndexOutOfBounds {
+            index: index.value,
+            len,
+        }))
+    } else {
+        Ok(index.value as usize)
+    }
+}
+
+// Checks the evaluated bounds of a slice of a sequence of length `len`. Missing bounds default to
+// the start and end of the sequence.
+fn slice_bounds(
+    slice_expression: &SliceExpression,
+    bounds: &[Option<Box<dyn Object>>],
+    len: usize,
+) -> Result<(usize, usize), EvaluatorError> {
+    let mut checked_bounds = [0, len as i64];
+    for ((checked_bound, bound), expression) in checked_bounds
+        .iter_mut()
+        .zip(bounds)
+        .zip([&slice_expression.start, &slice_expression.end])
+    {
+        if let (Some(bound), Some(expression)) = (bound, expression) {
+            let Some(integer) = bound.as_any().downcast_ref::<Integer>() else {
+                return Err(type_error("integer", bound.as_ref()).or_span(expression.span()));
+            };
+            *checked_bound = integer.value;
+        }
+    }
+    let [start, end] = checked_bounds;
+    if start < 0 || start > end || end as usize > len {
+        Err(EvaluatorError::new(EvaluatorErrorKind::InvalidSlice {
+            start,
+            end,
+            len,
+        }))
+    } else {
+        Ok((start as usize, end as usize))
+    }
+}
+
+// Gives `value` the name `name` if it is a function that doesn't have a name yet, so functions keep
+// the name they were first bound to when they are passed around.
+fn name_function(value: Box<dyn Object>, name: &str) -> Box<dyn Object> {
+    match value.as_any().downcast_ref::<Function>() {
+        Some(function) if function.name.is_none() => {
+            let mut function = function.clone();
+            function.name = Some(name.to_string());
+            Box::new(function)
+        }
+        _ => value,
+    }
+}
+
+/// Returns the hash key of `key`, or an error if `key` can't be used as a hash key.
+pub(crate) fn hash_key(key: &dyn Object) -> Result<HashKey, EvaluatorError> {
+    key.hash_key().ok_or_else(|| {
+        EvaluatorError::new(EvaluatorErrorKind::UnhashableKey {
+            found: key.type_name(),
+        })
+    })
+}
+
+// Returns an address in the stack frame of this function, which is as deep in the native stack as
+// its caller
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+fn type_error(expected: &'static str, found: &dyn Object) -> EvaluatorError {
+    EvaluatorError::new(EvaluatorErrorKind::TypeMismatch {
+        expected,
+        found: found.type_name(),
+    })
+}
@@ -1,13 +1,18 @@
 #[cfg(test)]
-use crate::evaluator::Evaluator;
+use crate::evaluator::environment::{self, Environment};
 #[cfg(test)]
-use crate::evaluator::environment::Environment;
+use crate::evaluator::{
+    Arity, CancellationHandle, DEFAULT_MAX_CALL_DEPTH, DEFAULT_MAX_STACK_SIZE, Evaluator,
+    EvaluatorError, EvaluatorErrorKind, OverflowBehavior,
+};
 #[cfg(test)]
 use crate::lexer::Lexer;
 #[cfg(test)]
-use crate::object::{Array, Boolean, Integer, Null, Object};
+use crate::object::{Array, Boolean, Integer, Null, Object, StringObject};
 #[cfg(test)]
 use crate::parser::Parser;
+#[cfg(test)]
+use crate::token::Span;
 
 #[test]
 fn integer_literal_evaluation() {
@@ -23,7 +28,7 @@ fn integer_literal_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -33,7 +38,7 @@ fn boolean_literal_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -41,7 +46,7 @@ fn boolean_literal_evaluation() {
 fn null_literal_evaluation() {
     let input = "null;";
     let evaluated = test_eval(input);
-    test_null_object(&evaluated);
+    test_null_object(evaluated.as_ref());
 }
 
 #[test]
@@ -55,7 +60,7 @@ fn bang_operator_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -71,7 +76,7 @@ fn minus_operator_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -98,7 +103,7 @@ fn integer_infix_expressions() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -125,7 +130,7 @@ fn comparison_infix_expressions() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -161,11 +166,40 @@ fn equality_infix_expressions() {
         ("(1 < 2) != false;", true),
         ("(1 > 2) == false;", true),
         ("(1 > 2) != true;", true),
+        // Numbers compare by value across types
+        ("1 == 1.0;", true),
+        ("2 != 2.5;", true),
+        // Strings, arrays and hashes compare structurally
+        (r#""abc" == "abc";"#, true),
+        (r#""abc" == "abd";"#, false),
+        ("[1, 2] == [1, 2];", true),
+        ("[1, 2] == [1, 2, 3];", false),
+        ("[1, [2, 3]] == [1, [2, 3.0]];", true),
+        ("[1, [2, 3]] != [1, [2, 4]];", true),
+        ("[] == [];", true),
+        (
+            r#"{"a": [1], 2: {true: null}} == {2: {true: null}, "a": [1]};"#,
+            true,
+        ),
+        (r#"{"a": 1} == {"a": 1, "b": 2};"#, false),
+        (r#"{"a": 1} == {"a": 2};"#, false),
+        (r#"{"a": 1} == {"b": 1};"#, false),
+        (r#"[1] == {0: 1};"#, false),
+        // Functions are only equal to themselves and to identical functions from the same scope
+        ("let f = fun(x) { x }; f == f;", true),
+        (
+            "let make = fun() { fun(x) { x } }; make() == make();",
+            false,
+        ),
+        ("fun(x) { x } == fun(x) { x };", true),
+        ("fun(x) { x } == fun(y) { y };", false),
+        ("len == len;", true),
+        ("len == push;", false),
     ];
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -181,10 +215,156 @@ fn division_by_zero_error() {
     let result = evaluator.eval(&program, &mut env);
     assert!(result.is_err());
     if let Err(error) = result {
-        assert!(error.error_message.contains("Division by zero"));
+        assert_eq!(error.kind, EvaluatorErrorKind::DivisionByZero);
+    }
+}
+
+#[test]
+fn modulo_evaluation() {
+    let tests = vec![
+        ("7 % 3;", "1"),
+        ("-7 % 3;", "-1"),
+        ("7 % -3;", "1"),
+        ("6 % 3;", "0"),
+        ("1 + 7 % 4 * 2;", "7"),
+        ("7.5 % 2;", "1.5"),
+        ("-9223372036854775807 - 1 % -1;", "-9223372036854775807"),
+        ("(-9223372036854775807 - 1) % -1;", "0"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    for input in ["5 % 0;", "5.5 % 0;", "5 % 0.0;"] {
+        let error = expect_eval_error(input);
+        assert_eq!(
+            error.kind,
+            EvaluatorErrorKind::ModuloByZero,
+            "Input: {input}"
+        );
     }
 }
 
+#[test]
+fn logical_operators() {
+    let tests = vec![
+        ("true && true;", true),
+        ("true && false;", false),
+        ("false || true;", true),
+        ("false || false;", false),
+        ("1 < 2 && 2 < 3;", true),
+        ("false || 1 > 2 || 3 == 3;", true),
+        // The operands can have any type, and are checked for truthiness like `if` conditions
+        ("1 && 2.5;", true),
+        ("null || 0;", false),
+        // The right operand isn't evaluated if the left operand decides the result
+        ("false && 1 / 0;", false),
+        ("true || undefined_variable;", true),
+        (
+            "let calls = fun(f) { f() }; false && calls(fun() { 1 / 0 });",
+            false,
+        ),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_boolean_object(evaluated.as_ref(), expected);
+    }
+
+    let error = expect_eval_error("true && 1 / 0;");
+    assert_eq!(error.kind, EvaluatorErrorKind::DivisionByZero);
+    let error = expect_eval_error("false || undefined_variable;");
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::UnknownIdentifier {
+            name: "undefined_variable".to_string()
+        }
+    );
+}
+
+#[test]
+fn loops() {
+    let tests = vec![
+        ("let f = fun() { while (true) { return 1; } }; f();", "1"),
+        ("while (true) { break; }", "null"),
+        (
+            "let f = fun() { for i in range(0, 10) { if i == 3 { return i; } } }; f();",
+            "3",
+        ),
+        (
+            "let f = fun(items) { for x in items { if x % 2 == 0 { continue; } return x; } }; f([2, 4, 5, 6]);",
+            "5",
+        ),
+        ("for x in [] { 1 / 0; }", "null"),
+        ("for x in range(5, 0) { 1 / 0; }", "null"),
+        ("while (false) { 1 / 0; }", "null"),
+        (
+            "for x in [1, 2] { if x == 1 { continue; } break; 1 / 0; }",
+            "null",
+        ),
+        // Loops can return from the function they are in, including from nested loops
+        (
+            r#"let find = fun(words, letter) { for word in words { for c in word { if c == letter { return word; } } } }; find(["abc", "def", "ghi"], "e");"#,
+            r#""def""#,
+        ),
+        // Hashes are iterated in the same order as `keys` returns
+        (
+            r#"let f = fun() { for k in {"b": 1, "a": 2, 3: 4} { return k; } }; f();"#,
+            "3",
+        ),
+        (
+            r#"let f = fun() { for c in "héllo" { if c != "h" { return c; } } }; f();"#,
+            r#""é""#,
+        ),
+        // Each iteration has its own binding of the loop variable
+        (
+            "let f = fun() { for i in [1, 2, 3] { if i == 2 { return fun() { i }; } } }; f()();",
+            "2",
+        ),
+        // break only stops the innermost loop
+        (
+            "let f = fun() { for i in range(0, 3) { for j in range(0, 3) { break; } if i == 2 { return i; } } }; f();",
+            "2",
+        ),
+        // break, continue and return in an if expression whose value is used end the loop
+        // iteration or function instead of becoming the value
+        (
+            "var i = 0; while (i < 5) { let x = if (i == 2) { break; }; i += 1; } i;",
+            "2",
+        ),
+        (
+            "var items = []; for j in [1, 2, 3] { items = push(items, [if (j == 2) { continue; } else { j }]); } items;",
+            "[[1], [3]]",
+        ),
+        (
+            "var n = 0; while (true) { n += 1; push([], if (n == 3) { break; } else { n }); } n;",
+            "3",
+        ),
+        (
+            "let f = fun(x) { let y = 1 + if (x > 0) { return x; } else { 0 }; y * 10 }; [f(2), f(0)];",
+            "[2, 10]",
+        ),
+        ("range(1, 4);", "range(1, 4)"),
+        ("range(1, 4) == range(1, 4);", "true"),
+        ("range(1, 4) == range(1, 5);", "false"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    let error = expect_eval_error("for x in 5 { x; }");
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::TypeMismatch {
+            expected: "array, string, hash or range",
+            found: "integer"
+        }
+    );
+    let error = expect_eval_error("let n = 0; while (1 / n) { }");
+    assert_eq!(error.kind, EvaluatorErrorKind::DivisionByZero);
+}
+
 #[test]
 fn unknown_operator_errors() {
     let tests = vec![
@@ -232,8 +412,8 @@ fn if_expressions() {
     for (input, expected) in tests {
         let evaluated = test_eval(input);
         match expected {
-            Some(value) => test_integer_object(&evaluated, value),
-            None => test_null_object(&evaluated),
+            Some(value) => test_integer_object(evaluated.as_ref(), value),
+            None => test_null_object(evaluated.as_ref()),
         }
     }
 }
@@ -255,7 +435,7 @@ fn complex_expressions() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -284,7 +464,7 @@ fn return_statements() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -305,7 +485,7 @@ fn return_statements_with_conditionals() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -322,7 +502,7 @@ fn return_statements_with_boolean_values() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_boolean_object(&evaluated, expected);
+        test_boolean_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -330,7 +510,7 @@ fn return_statements_with_boolean_values() {
 fn return_null_value() {
     let input = "return null;";
     let evaluated = test_eval(input);
-    test_null_object(&evaluated);
+    test_null_object(evaluated.as_ref());
 }
 
 // Helper functions
@@ -349,20 +529,21 @@ fn test_eval(input: &str) -> Box<dyn Object> {
 }
 
 #[cfg(test)]
-fn expect_eval_error(input: &str) {
+fn expect_eval_error(input: &str) -> EvaluatorError {
     let lexer = Lexer::new(input);
     let mut parser = Parser::new(lexer);
     let program = parser.parse_program();
     let mut evaluator = Evaluator::new();
     let mut env = Environment::new();
 
-    if let Ok(val) = evaluator.eval(&program, &mut env) {
-        panic!("Expected error, got {}", val.inspect());
+    match evaluator.eval(&program, &mut env) {
+        Ok(val) => panic!("Expected error, got {}", val.inspect()),
+        Err(error) => error,
     }
 }
 
 #[cfg(test)]
-fn test_integer_object(obj: &Box<dyn Object>, expected: i64) {
+fn test_integer_object(obj: &dyn Object, expected: i64) {
     if let Some(integer) = obj.as_any().downcast_ref::<Integer>() {
         assert_eq!(integer.value, expected, "Integer value mismatch");
     } else {
@@ -371,7 +552,7 @@ fn test_integer_object(obj: &Box<dyn Object>, expected: i64) {
 }
 
 #[cfg(test)]
-fn test_boolean_object(obj: &Box<dyn Object>, expected: bool) {
+fn test_boolean_object(obj: &dyn Object, expected: bool) {
     if let Some(boolean) = obj.as_any().downcast_ref::<Boolean>() {
         assert_eq!(boolean.value, expected, "Boolean value mismatch");
     } else {
@@ -380,14 +561,14 @@ fn test_boolean_object(obj: &Box<dyn Object>, expected: bool) {
 }
 
 #[cfg(test)]
-fn test_null_object(obj: &Box<dyn Object>) {
+fn test_null_object(obj: &dyn Object) {
     if !obj.as_any().is::<Null>() {
         panic!("Expected Null object, got different type");
     }
 }
 
 #[cfg(test)]
-fn test_array_object(obj: &Box<dyn Object>, expected: &str) {
+fn test_array_object(obj: &dyn Object, expected: &str) {
     if let Some(array) = obj.as_any().downcast_ref::<Array>() {
         assert_eq!(array.inspect(), expected, "Array value mismatch");
     } else {
@@ -416,7 +597,7 @@ fn array_index_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -435,12 +616,12 @@ fn array_index_with_mixed_types() {
     for (input, expected) in tests {
         let evaluated = test_eval(input);
         match expected {
-            "true" => test_boolean_object(&evaluated, true),
-            "false" => test_boolean_object(&evaluated, false),
-            "null" => test_null_object(&evaluated),
+            "true" => test_boolean_object(evaluated.as_ref(), true),
+            "false" => test_boolean_object(evaluated.as_ref(), false),
+            "null" => test_null_object(evaluated.as_ref()),
             _ => {
                 if let Ok(int_val) = expected.parse::<i64>() {
-                    test_integer_object(&evaluated, int_val);
+                    test_integer_object(evaluated.as_ref(), int_val);
                 }
             }
         }
@@ -485,7 +666,7 @@ fn factorial_function() {
     "#;
 
     let evaluated = test_eval(input);
-    test_integer_object(&evaluated, 120);
+    test_integer_object(evaluated.as_ref(), 120);
 }
 
 #[test]
@@ -504,7 +685,788 @@ fn fibonacci_function() {
     "#;
 
     let evaluated = test_eval(input);
-    test_integer_object(&evaluated, 21);
+    test_integer_object(evaluated.as_ref(), 21);
+}
+
+#[test]
+fn mutually_recursive_functions() {
+    let tests = vec![
+        (
+            "let rec is_even = fun(n) { if (n == 0) { true } else { is_odd(n - 1) } }
+            and is_odd = fun(n) { if (n == 0) { false } else { is_even(n - 1) } };
+            [is_even(10), is_odd(10), is_even(7), is_odd(7)];",
+            "[true, false, false, true]",
+        ),
+        // Recursion keeps working when the function is called through another name, even after
+        // its own name is rebound
+        (
+            "let rec countdown = fun(n) { if (n == 0) { [] } else { push(countdown(n - 1), n) } };
+            let f = countdown;
+            let countdown = 5;
+            let apply = fun(g, x) { g(x) };
+            apply(f, 3);",
+            "[1, 2, 3]",
+        ),
+        // Recursive functions defined inside other functions
+        (
+            "let sum = fun(arr) {
+                let rec go = fun(i, acc) {
+                    if (i == len(arr)) { acc } else { go(i + 1, acc + arr[i]) }
+                };
+                [go(0, 0)]
+            };
+            sum([1, 2, 3, 4]);",
+            "[10]",
+        ),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_array_object(evaluated.as_ref(), expected);
+    }
+}
+
+#[test]
+fn tail_calls() {
+    // These recurse far deeper than the Rust stack would allow if tail calls used it
+    let tests = vec![
+        (
+            "let rec count = fun(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } };
+            count(20000, 0);",
+            20000,
+        ),
+        // Through return statements
+        (
+            "let rec count = fun(n) { if (n == 0) { return 0; } return count(n - 1); };
+            count(20000);",
+            0,
+        ),
+        // Through nested ifs
+        (
+            "let rec count = fun(n) {
+                if (n == 0) { 7 } else { if (n % 2 == 0) { let m = n - 1; count(m) } else { count(n - 1) } }
+            };
+            count(20000);",
+            7,
+        ),
+        // Between different functions
+        (
+            "let rec is_even = fun(n) { if (n == 0) { 1 } else { is_odd(n - 1) } }
+            and is_odd = fun(n) { if (n == 0) { 0 } else { is_even(n - 1) } };
+            is_even(20000);",
+            1,
+        ),
+        // Builtins and calls that aren't in tail position still work
+        ("let f = fun(x) { len(x) }; f([1, 2]);", 2),
+        (
+            "let rec count = fun(n) { while (true) { if (n > 0) { return count(n - 1); } break; } n };
+            count(20);",
+            0,
+        ),
+        (
+            "let rec fact = fun(n) { if (n == 0) { 1 } else { n * fact(n - 1) } }; fact(5);",
+            120,
+        ),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_integer_object(evaluated.as_ref(), expected);
+    }
+
+    // A return statement that isn't in tail position makes its call right away, even where it is
+    // in another value
+    let input = "let g = fun(x) { x * 2 };
+        let f = fun(x) { [if (true) { return g(x); } else { 1 }] };
+        f(3);";
+    test_integer_object(test_eval(input).as_ref(), 6);
+
+    // Tail calls with the wrong number of arguments are reported like other calls
+    let error = expect_eval_error("let f = fun(x) { x }; let g = fun() { f() }; g();");
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::ArityMismatch {
+            function: Some("f".to_string()),
+            expected: Arity::Exact(1),
+            found: 0,
+        }
+    );
+    assert_eq!(error.backtrace.len(), 1);
+}
+
+#[test]
+fn recursion_limit() {
+    let eval_with_limit = |input: &str, limit: usize| {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        let mut evaluator = Evaluator::new();
+        evaluator.set_max_call_depth(limit);
+        let result = evaluator.eval(&program, &mut Environment::new());
+        (evaluator, result)
+    };
+
+    let input = "let rec f = fun(n) { 1 + f(n + 1) };\nf(0);";
+    let (mut evaluator, result) = eval_with_limit(input, 50);
+    let error = result.err().expect("Expected an error");
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::RecursionLimitExceeded { depth: 50 }
+    );
+    assert_eq!(error.code(), "R0017");
+    let span = error.span.expect("Expected error to have a span");
+    assert_eq!(&input[span.start.offset..span.end.offset], "f(n + 1)");
+    // The error carries the calls that were in progress
+    assert_eq!(error.backtrace.len(), 50);
+    assert!(
+        error
+            .backtrace
+            .iter()
+            .all(|frame| frame.function.as_deref() == Some("f"))
+    );
+
+    // The evaluator can still be used after the error
+    let program = Parser::new(Lexer::new("let g = fun(x) { x }; g(3);")).parse_program();
+    let evaluated = evaluator.eval(&program, &mut Environment::new()).unwrap();
+    test_integer_object(evaluated.as_ref(), 3);
+
+    // Recursion up to the limit is allowed, and tail calls don't count towards it
+    let tests = vec![
+        "let rec f = fun(n) { if (n == 1) { 1 } else { 1 + f(n - 1) } }; f(50);",
+        "let rec f = fun(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(1000);",
+    ];
+    for input in tests {
+        let (_, result) = eval_with_limit(input, 50);
+        assert!(result.is_ok(), "Input: {input}");
+    }
+
+    // With the default limits, deep recursion is an error on a thread with the default stack size,
+    // like the one this test runs on. Calls nested in deep expressions use more stack each.
+    let tests = vec![
+        "let rec f = fun(n) { 1 + f(n + 1) }; f(0);",
+        "let rec f = fun(n) { [[[[[[[[[[1 + f(n + 1)]]]]]]]]]] }; f(0);",
+        "let rec f = fun(n) { let g = fun() { f(n + 1) }; 1 + g() }; f(0);",
+    ];
+    for input in tests {
+        let error = expect_eval_error(input);
+        let EvaluatorErrorKind::StackLimitExceeded { depth, limit } = error.kind else {
+            panic!("Expected a stack limit error, got {error}. Input: {input}");
+        };
+        assert_eq!(limit, DEFAULT_MAX_STACK_SIZE, "Input: {input}");
+        assert_eq!(error.code(), "R0021", "Input: {input}");
+        assert!(depth > 0, "Input: {input}");
+        assert_eq!(error.backtrace.len(), depth, "Input: {input}");
+    }
+
+    // A bigger stack allows recursion up to the default call depth
+    let error = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            let program = Parser::new(Lexer::new("let rec f = fun(n) { 1 + f(n + 1) }; f(0);"))
+                .parse_program();
+            let mut evaluator = Evaluator::new();
+            evaluator.set_max_stack_size(56 * 1024 * 1024);
+            evaluator
+                .eval(&program, &mut Environment::new())
+                .err()
+                .unwrap()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::RecursionLimitExceeded {
+            depth: DEFAULT_MAX_CALL_DEPTH
+        }
+    );
+}
+
+#[test]
+fn execution_limits() {
+    let eval_with_limit = |evaluator: &mut Evaluator, input: &str| {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        evaluator.eval(&program, &mut Environment::new())
+    };
+
+    // Programs that never finish on their own
+    let tests = vec![
+        "let rec f = fun() { f() }; f();",
+        "while (true) {}",
+        "for i in range(0, 9223372036854775807) {}",
+        "let f = fun(n) { var m = n; while (true) { m += 1; } }; f(0);",
+    ];
+    for input in tests {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_step_limit(Some(10000));
+        let error = eval_with_limit(&mut evaluator, input).err().expect(input);
+        assert_eq!(
+            error.kind,
+            EvaluatorErrorKind::ExecutionLimitExceeded { limit: 10000 },
+            "Input: {input}"
+        );
+        assert_eq!(error.code(), "R0018");
+    }
+
+    // Each evaluation gets the full budget, including calls made from Rust
+    let mut evaluator = Evaluator::new();
+    let input = "var x = 0; while (x < 100) { x += 1; } x";
+    evaluator.set_step_limit(Some(1000));
+    for _ in 0..3 {
+        let evaluated = eval_with_limit(&mut evaluator, input).unwrap();
+        test_integer_object(evaluated.as_ref(), 100);
+    }
+    let mut env = Environment::new();
+    let program = Parser::new(Lexer::new(&format!("let f = fun() {{ {input} }};"))).parse_program();
+    evaluator.eval(&program, &mut env).unwrap();
+    let f = env.get("f").unwrap();
+    for _ in 0..3 {
+        let evaluated = evaluator
+            .call_function(f.as_ref(), vec![], Span::default())
+            .unwrap();
+        test_integer_object(evaluated.as_ref(), 100);
+    }
+    evaluator.set_step_limit(Some(500));
+    assert!(eval_with_limit(&mut evaluator, input).is_err());
+    evaluator.set_step_limit(None);
+    assert!(eval_with_limit(&mut evaluator, input).is_ok());
+}
+
+#[test]
+fn cancellation() {
+    let mut evaluator = Evaluator::new();
+    let handle = CancellationHandle::new();
+    evaluator.set_cancellation_handle(handle.clone());
+    let program = Parser::new(Lexer::new("1 + 2;")).parse_program();
+    assert!(evaluator.eval(&program, &mut Environment::new()).is_ok());
+
+    // Cancelling from another thread stops a program that is running
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        handle.cancel();
+    });
+    let program = Parser::new(Lexer::new("let rec f = fun(n) { f(n + 1) }; f(0);")).parse_program();
+    let error = evaluator
+        .eval(&program, &mut Environment::new())
+        .err()
+        .expect("Expected the program to be cancelled");
+    canceller.join().unwrap();
+    assert_eq!(error.kind, EvaluatorErrorKind::Cancelled);
+    assert_eq!(error.code(), "R0019");
+    assert_eq!(error.backtrace.len(), 1);
+
+    // The handle stays cancelled until the evaluator is given a new one
+    let program = Parser::new(Lexer::new("1 + 2;")).parse_program();
+    let error = evaluator
+        .eval(&program, &mut Environment::new())
+        .err()
+        .expect("Expected an error");
+    assert_eq!(error.kind, EvaluatorErrorKind::Cancelled);
+    evaluator.set_cancellation_handle(CancellationHandle::new());
+    assert!(evaluator.eval(&program, &mut Environment::new()).is_ok());
+}
+
+#[test]
+fn memory_limit() {
+    let eval_with_limit = |evaluator: &mut Evaluator, env: &mut Environment, input: &str| {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        evaluator.eval(&program, env)
+    };
+
+    // Programs that allocate without bound
+    let tests = vec![
+        r#"var a = []; while (true) { a = push(a, "x"); }"#,
+        r#"var s = "x"; while (true) { s = s + s; }"#,
+        r#"var h = {}; var i = 0; while (true) { h[i] = [i]; i += 1; }"#,
+        "var a = []; while (true) { a = push(a, fun(x) { x }); }",
+    ];
+    for input in tests {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_memory_limit(Some(100_000));
+        let error = eval_with_limit(&mut evaluator, &mut Environment::new(), input)
+            .err()
+            .expect(input);
+        assert_eq!(
+            error.kind,
+            EvaluatorErrorKind::MemoryLimitExceeded { limit: 100_000 },
+            "Input: {input}"
+        );
+        assert_eq!(error.code(), "R0020");
+    }
+
+    // Only live objects are counted, so memory that is released can be used again
+    let mut evaluator = Evaluator::new();
+    let mut env = Environment::new();
+    evaluator.set_memory_limit(Some(100_000));
+    let input = "let fill = fun() { var a = []; while (true) { a = push(a, 1); } }; fill();";
+    assert!(eval_with_limit(&mut evaluator, &mut env, input).is_err());
+    assert!(evaluator.memory_usage() < 1000);
+    let input = r#"var s = "x"; for i in range(0, 10) { s = s + s; } len(s)"#;
+    let evaluated = eval_with_limit(&mut evaluator, &mut env, input).unwrap();
+    test_integer_object(evaluated.as_ref(), 1024);
+    let used = evaluator.memory_usage();
+    assert!(used > 1024);
+    eval_with_limit(&mut evaluator, &mut env, r#"s = "";"#).unwrap();
+    assert!(evaluator.memory_usage() <= used - 1024);
+    // `fill` holds on to the environment it's bound in, so the environment is freed by the cycle
+    // collector
+    drop(env);
+    environment::collect_cycles();
+    assert_eq!(evaluator.memory_usage(), 0);
+
+    // Builtins that make results much bigger than their arguments fail before making them
+    let tests = vec![
+        "chars(s)",
+        "split(s, \"x\")",
+        "replace(s, \"x\", \"xxxxxxxxxx\")",
+        "join([s, s, s, s, s, s, s], s)",
+        "s + s + s + s + s + s + s",
+    ];
+    for input in tests {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_memory_limit(Some(100_000));
+        let mut env = Environment::new();
+        let setup = r#"var s = "x"; for i in range(0, 14) { s = s + s; }"#;
+        eval_with_limit(&mut evaluator, &mut env, setup).unwrap();
+        let error = eval_with_limit(&mut evaluator, &mut env, input)
+            .err()
+            .expect(input);
+        assert_eq!(
+            error.kind,
+            EvaluatorErrorKind::MemoryLimitExceeded { limit: 100_000 },
+            "Input: {input}"
+        );
+        assert!(evaluator.memory_usage() <= 100_000, "Input: {input}");
+    }
+
+    // Big integers are counted by their digits
+    let mut evaluator = Evaluator::new();
+    evaluator.set_overflow_behavior(OverflowBehavior::Promote);
+    evaluator.set_memory_limit(Some(usize::MAX));
+    let mut env = Environment::new();
+    let input = "var x = 2; for i in range(0, 16) { x = x * x; }";
+    eval_with_limit(&mut evaluator, &mut env, input).unwrap();
+    // 2 ** 65536 has 19729 decimal digits
+    assert!(evaluator.memory_usage() > 19729 / 9 * 4);
+    drop(env);
+    evaluator.set_memory_limit(Some(10_000));
+    let input = "var y = 2; while (true) { y = y * y; }";
+    let error = eval_with_limit(&mut evaluator, &mut Environment::new(), input)
+        .err()
+        .expect(input);
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::MemoryLimitExceeded { limit: 10_000 }
+    );
+
+    // Scopes that are only kept alive by closures stored in them don't count towards the limit
+    let mut evaluator = Evaluator::new();
+    evaluator.set_memory_limit(Some(200_000));
+    let input = "let f = fun(x) { let g = fun() { x }; g() }; for i in range(0, 20000) { f(i); }";
+    assert!(eval_with_limit(&mut evaluator, &mut Environment::new(), input).is_ok());
+
+    // Nothing is counted without a limit
+    let mut evaluator = Evaluator::new();
+    eval_with_limit(
+        &mut evaluator,
+        &mut Environment::new(),
+        "let a = [1, 2, 3];",
+    )
+    .unwrap();
+    assert_eq!(evaluator.memory_usage(), 0);
+}
+
+#[test]
+fn closure_cycles_are_freed() {
+    // Closures stored in the scopes they capture: in a function call, in a loop iteration and in
+    // the scope of a `let rec` in a function call
+    let tests = vec![
+        "let f = fun(x) { let g = fun() { x }; g() }; for i in range(0, 3000) { f(i); }",
+        "for i in range(0, 3000) { let g = fun() { i }; g(); }",
+        "let f = fun(x) { let rec g = fun(n) { if (n > 0) { g(n - 1) } else { x } }; g(2) }; for i in range(0, 3000) { f(i); }",
+    ];
+    for input in tests {
+        // The memory limit is only set so that memory is counted
+        let mut evaluator = Evaluator::new();
+        evaluator.set_memory_limit(Some(usize::MAX));
+        let mut env = Environment::new();
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        evaluator.eval(&program, &mut env).unwrap();
+        environment::collect_cycles();
+        assert!(evaluator.memory_usage() < 1000, "Input: {input}");
+        // The global scope is part of a cycle as well once `f` is bound in it
+        drop(env);
+        environment::collect_cycles();
+        assert_eq!(evaluator.memory_usage(), 0, "Input: {input}");
+    }
+}
+
+#[test]
+fn call_arbitrary_callees() {
+    let tests = vec![
+        (
+            "let make_adder = fun(x) { fun(y) { x + y } }; make_adder(1)(2);",
+            3,
+        ),
+        (
+            "let handlers = [fun(x) { x + 1 }, fun(x) { x * 2 }]; handlers[1](21);",
+            42,
+        ),
+        (
+            "let f = fun(x) { x + 1 }; let g = fun(x) { x - 1 }; (if (false) { f } else { g })(5);",
+            4,
+        ),
+        // Builtins are values that can be bound and passed around like any other function
+        ("let length = len; length([1, 2, 3]);", 3),
+        ("let apply = fun(f, x) { f(x) }; apply(len, [1, 2]);", 2),
+        ("[len][0]([1]);", 1),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_integer_object(evaluated.as_ref(), expected);
+    }
+
+    let error = expect_eval_error("let f = fun() { 1 }; f()(2);");
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::NotCallable { found: "integer" }
+    );
+    let span = error.span.expect("Expected error to have a span");
+    assert_eq!(span.start.column, 22);
+    assert_eq!(span.end.column, 25);
+}
+
+#[test]
+fn hash_evaluation() {
+    let tests = vec![
+        ("{};", "{}"),
+        (
+            r#"let two = "two"; {"one": 10 - 9, two: 1 + 1, 3: 3, true: [4], "one": 1};"#,
+            r#"{true: [4], 3: 3, "one": 1, "two": 2}"#,
+        ),
+        (r#"{"a": {1: null}}["a"];"#, "{1: null}"),
+        (
+            r#"let h = {"k": 5, 1: "one", false: 0}; [h["k"], h[1], h[false]];"#,
+            r#"[5, "one", 0]"#,
+        ),
+        (r#"keys({"b": 1, "a": 2, 0: 3});"#, r#"[0, "a", "b"]"#),
+        (r#"values({"b": 1, "a": 2, 0: 3});"#, "[3, 2, 1]"),
+        (
+            r#"let h = {"a": 1}; [has_key(h, "a"), has_key(h, "b"), has_key(h, 1)];"#,
+            "[true, false, false]",
+        ),
+        // insert and remove return new hashes and leave the original unchanged
+        (
+            r#"let h = {"a": 1}; let h2 = insert(h, "b", 2); let h3 = insert(h2, "a", 0); [h, h2, h3];"#,
+            r#"[{"a": 1}, {"a": 1, "b": 2}, {"a": 0, "b": 2}]"#,
+        ),
+        (
+            r#"let h = {"a": 1, "b": 2}; [remove(h, "a"), remove(h, "c"), h];"#,
+            r#"[{"b": 2}, {"a": 1, "b": 2}, {"a": 1, "b": 2}]"#,
+        ),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    let tests = vec![
+        (
+            "{[1]: 2};",
+            EvaluatorErrorKind::UnhashableKey { found: "array" },
+            "[1]",
+        ),
+        (
+            r#"{"a": 1}[fun() { 1 }];"#,
+            EvaluatorErrorKind::UnhashableKey { found: "function" },
+            "fun() { 1 }",
+        ),
+        (
+            r#"let h = {"a": 1}; h["b"];"#,
+            EvaluatorErrorKind::KeyNotFound {
+                key: r#""b""#.to_string(),
+            },
+            r#"h["b"]"#,
+        ),
+        (
+            "1[0];",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "array, string or hash",
+                found: "integer",
+            },
+            "1",
+        ),
+        (
+            "insert([], 1, 2);",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "hash",
+                found: "array",
+            },
+            "insert([], 1, 2)",
+        ),
+    ];
+    for (input, expected_kind, expected_source) in tests {
+        let error = expect_eval_error(input);
+        assert_eq!(error.kind, expected_kind, "Input: {input}");
+        let span = error.span.expect("Expected error to have a span");
+        assert_eq!(&input[span.start.offset..span.end.offset], expected_source);
+    }
+}
+
+#[test]
+fn float_evaluation() {
+    let tests = vec![
+        ("3.14;", "3.14"),
+        ("2.0;", "2.0"),
+        ("-1.5;", "-1.5"),
+        ("0.1 + 0.2 > 0.3;", "true"),
+        ("1.5 * 2;", "3.0"),
+        ("7 / 2.0;", "3.5"),
+        ("7 / 2;", "3"),
+        ("1 - 0.25;", "0.75"),
+        ("1e3 + 1;", "1001.0"),
+        ("2 < 2.5;", "true"),
+        ("2.5 >= 3;", "false"),
+        ("1 == 1.0;", "true"),
+        ("1.5 != 1.5;", "false"),
+        ("1.0 == true;", "false"),
+        ("if (0.0) { 1 } else { 2 };", "2"),
+        ("int(3.99);", "3"),
+        ("int(-3.99);", "-3"),
+        ("int(5);", "5"),
+        ("float(2);", "2.0"),
+        ("float(2.5);", "2.5"),
+        ("round(2.5);", "3.0"),
+        ("round(-2.4);", "-2.0"),
+        ("floor(-2.5);", "-3.0"),
+        ("ceil(2.1);", "3.0"),
+        ("[round(4), floor(4), ceil(4)];", "[4, 4, 4]"),
+        ("let avg = fun(a, b) { (a + b) / 2.0 }; avg(3, 4);", "3.5"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    let tests = vec![
+        ("1.5 / 0;", EvaluatorErrorKind::DivisionByZero),
+        (
+            "1.5 + true;",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "float",
+                found: "boolean",
+            },
+        ),
+        (
+            "[1] * 2.0;",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "float",
+                found: "array",
+            },
+        ),
+        (
+            "floor(\"1\");",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "number",
+                found: "string",
+            },
+        ),
+        (
+            "int(1e300);",
+            EvaluatorErrorKind::InvalidArgument {
+                function: "int".to_string(),
+                reason: "1e300 is out of range for an integer".to_string(),
+            },
+        ),
+    ];
+    for (input, expected_kind) in tests {
+        assert_eq!(
+            expect_eval_error(input).kind,
+            expected_kind,
+            "Input: {input}"
+        );
+    }
+}
+
+#[test]
+fn assignment() {
+    let tests = vec![
+        ("var x = 1; x = 2; x;", "2"),
+        ("var x = 1; x = x + 1;", "2"),
+        ("var x = 5; x += 2; x -= 1; x *= 3; x /= 2; x %= 5; x;", "4"),
+        (r#"var s = "a"; s += "b"; s;"#, r#""ab""#),
+        ("var x = 1; var y = 2; x = y = 3; [x, y];", "[3, 3]"),
+        // Assignments update the nearest enclosing binding
+        (
+            "var i = 0; var total = 0; while (i < 4) { i += 1; total += i; } [i, total];",
+            "[4, 10]",
+        ),
+        ("var x = 1; let f = fun() { x = 2; }; f(); x;", "2"),
+        (
+            "var x = 1; let f = fun() { var x = 5; x = 2; }; f(); x;",
+            "1",
+        ),
+        (
+            "let counter = fun() { var n = 0; fun() { n += 1; n } }; let c = counter(); c(); c(); c();",
+            "3",
+        ),
+        // Arrays and hashes are values, so assigning to an item doesn't change other copies
+        (
+            "var a = [1, 2]; let b = a; a[0] = 10; [a, b];",
+            "[[10, 2], [1, 2]]",
+        ),
+        ("var a = [[1, 2], [3]]; a[0][1] += 5; a;", "[[1, 7], [3]]"),
+        (
+            r#"var h = {"k": 1}; h["k"] += 1; h["new"] = [1]; h["new"][0] = 2; h;"#,
+            r#"{"k": 2, "new": [2]}"#,
+        ),
+        (
+            r#"var h = {}; var i = 0; for k in ["a", "b"] { h[k] = i; i += 1; } h;"#,
+            r#"{"a": 0, "b": 1}"#,
+        ),
+        ("var a = [0]; a[0] = 5;", "5"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    let tests = vec![
+        (
+            "x = 1;",
+            EvaluatorErrorKind::UndeclaredAssignment {
+                name: "x".to_string(),
+            },
+        ),
+        (
+            "let f = fun() { y += 1; }; f();",
+            EvaluatorErrorKind::UndeclaredAssignment {
+                name: "y".to_string(),
+            },
+        ),
+        (
+            "a[0] = 1;",
+            EvaluatorErrorKind::UndeclaredAssignment {
+                name: "a".to_string(),
+            },
+        ),
+        (
+            "var a = [1]; a[1] = 2;",
+            EvaluatorErrorKind::IndexOutOfBounds { index: 1, len: 1 },
+        ),
+        (
+            r#"var s = "abc"; s[0] = "x";"#,
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "array or hash",
+                found: "string",
+            },
+        ),
+        (
+            r#"var h = {}; h["k"] += 1;"#,
+            EvaluatorErrorKind::KeyNotFound {
+                key: r#""k""#.to_string(),
+            },
+        ),
+        (
+            "var x = true; x += 1;",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "boolean",
+            },
+        ),
+    ];
+    for (input, expected) in tests {
+        let error = expect_eval_error(input);
+        assert_eq!(error.kind, expected, "Input: {input}");
+    }
+}
+
+#[test]
+fn immutable_bindings() {
+    // Shadowing an immutable binding with a mutable one is allowed
+    let tests = vec![
+        ("let x = 1; var x = x; x = 2; x", 2),
+        (
+            "var x = 1; let f = fun() { var x = 10; x += 1; x }; f() + x",
+            12,
+        ),
+        ("var x = 1; let f = fun() { x = 5; }; f(); x", 5),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_integer_object(evaluated.as_ref(), expected);
+    }
+
+    // The error points at the declaration of the binding that was assigned to
+    let tests = vec![
+        ("let x = 1;\nx = 2;", "x", (1, 5)),
+        ("let x = 1;\nx += 2;", "x", (1, 5)),
+        ("let a = [1];\na[0] = 2;", "a", (1, 5)),
+        ("let f = fun(n) {\nn = 1;\n};\nf(0);", "n", (1, 13)),
+        ("for i in range(0, 2) {\ni += 1;\n}", "i", (1, 5)),
+        ("let rec f = fun() {\nf = 1;\n};\nf();", "f", (1, 9)),
+        (
+            "var x = 1;\nlet f = fun() {\nlet x = 2;\nx = 3;\n};\nf();",
+            "x",
+            (3, 5),
+        ),
+    ];
+    for (input, expected_name, (line, column)) in tests {
+        let error = expect_eval_error(input);
+        match &error.kind {
+            EvaluatorErrorKind::ImmutableAssignment { name, declared_at } => {
+                assert_eq!(name, expected_name, "Input: {input}");
+                let declared_at = declared_at.expect("declaration span");
+                assert_eq!(
+                    (declared_at.start.line, declared_at.start.column),
+                    (line, column),
+                    "Input: {input}"
+                );
+            }
+            other => panic!("Expected ImmutableAssignment for {input}, got {other:?}"),
+        }
+    }
+
+    let error = expect_eval_error("let x = 1;\nx = 2;");
+    assert_eq!(error.span.unwrap().start.line, 2);
+    assert_eq!(
+        error.to_string(),
+        "2:1: Assignment to immutable variable: x, declared at 1:5"
+    );
+}
+
+#[test]
+fn closures() {
+    let tests = vec![
+        // Closures capture the scope of the call that created them
+        (
+            "let make_adder = fun(x) { fun(y) { x + y } };
+            let add_two = make_adder(2);
+            let add_ten = make_adder(10);
+            add_two(1) + add_ten(1);",
+            14,
+        ),
+        // Closures see bindings added to their scope after they were created
+        (
+            "let f = fun() { g() * 2 };
+            let g = fun() { 21 };
+            f();",
+            42,
+        ),
+        (
+            "let x = 1;
+            let get_x = fun() { x };
+            let x = 2;
+            get_x();",
+            2,
+        ),
+        // Parameters of one call don't leak into other calls of the same function
+        (
+            "let rec sum_to = fun(n) { if (n == 0) { 0 } else { n + sum_to(n - 1) } };
+            sum_to(3) + sum_to(2);",
+            9,
+        ),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        test_integer_object(evaluated.as_ref(), expected);
+    }
 }
 
 #[test]
@@ -527,7 +1489,7 @@ fn array_literal_evaluation() {
 
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_array_object(&evaluated, expected);
+        test_array_object(evaluated.as_ref(), expected);
     }
 }
 
@@ -540,7 +1502,7 @@ fn array_push() {
     ];
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_array_object(&evaluated, expected);
+        test_array_object(evaluated.as_ref(), expected);
     }
     let tests = vec!["push([])", "push(1, 1)", "push()", "push([], 1, 2)"];
     for input in tests {
@@ -553,7 +1515,7 @@ fn array_tail() {
     let tests = vec![("tail([1])", "[]"), ("tail([1, 2])", "[2]")];
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_array_object(&evaluated, expected);
+        test_array_object(evaluated.as_ref(), expected);
     }
     let tests = vec!["tail([])", "tail(1)", "tail()", "tail([], 1)"];
     for input in tests {
@@ -566,6 +1528,344 @@ fn array_len() {
     let tests = vec![("len([])", 0), ("len([1])", 1), ("len([1,2,3])", 3)];
     for (input, expected) in tests {
         let evaluated = test_eval(input);
-        test_integer_object(&evaluated, expected);
+        test_integer_object(evaluated.as_ref(), expected);
+    }
+}
+
+#[test]
+fn structured_errors() {
+    let tests = vec![
+        (
+            "foo;",
+            EvaluatorErrorKind::UnknownIdentifier {
+                name: "foo".to_string(),
+            },
+            "R0001",
+        ),
+        (
+            "1 + true;",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "boolean",
+            },
+            "R0002",
+        ),
+        (
+            "-[1];",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "array",
+            },
+            "R0002",
+        ),
+        (
+            "[1, 2][-1];",
+            EvaluatorErrorKind::IndexOutOfBounds { index: -1, len: 2 },
+            "R0003",
+        ),
+        (
+            "fun(a, b) { a }(1);",
+            EvaluatorErrorKind::ArityMismatch {
+                function: None,
+                expected: Arity::Exact(2),
+                found: 1,
+            },
+            "R0004",
+        ),
+        (
+            "len([], []);",
+            EvaluatorErrorKind::ArityMismatch {
+                function: Some("len".to_string()),
+                expected: Arity::Exact(1),
+                found: 2,
+            },
+            "R0004",
+        ),
+        ("1 / 0;", EvaluatorErrorKind::DivisionByZero, "R0005"),
+        (
+            "let x = 1; x();",
+            EvaluatorErrorKind::NotCallable { found: "integer" },
+            "R0006",
+        ),
+        (
+            "tail([]);",
+            EvaluatorErrorKind::InvalidArgument {
+                function: "tail".to_string(),
+                reason: "expected a non-empty array".to_string(),
+            },
+            "R0008",
+        ),
+    ];
+
+    for (input, expected_kind, expected_code) in tests {
+        let error = expect_eval_error(input);
+        assert_eq!(error.kind, expected_kind, "Input: {input}");
+        assert_eq!(error.code(), expected_code, "Input: {input}");
+        assert!(error.span.is_some(), "Input: {input}");
+    }
+}
+
+#[test]
+fn error_spans_point_at_offending_expression() {
+    let input = "let x = 1;\nx + true;";
+    let error = expect_eval_error(input);
+    let span = error.span.expect("Expected error to have a span");
+    assert_eq!(&input[span.start.offset..span.end.offset], "true");
+    assert_eq!(
+        error.to_string(),
+        "2:5: Expected integer, found boolean instead"
+    );
+}
+
+#[test]
+fn runtime_errors_carry_backtrace() {
+    let input = "let helper = fun(x) { x + true };
+let rec countdown = fun(n) {
+    if (n == 0) { helper(n) } else { 1 + countdown(n - 1) }
+};
+countdown(2);";
+    let error = expect_eval_error(input);
+    let frames: Vec<_> = error
+        .backtrace
+        .iter()
+        .map(|frame| {
+            let call_site = &input[frame.call_site.start.offset..frame.call_site.end.offset];
+            (frame.function.as_deref(), call_site)
+        })
+        .collect();
+    assert_eq!(
+        frames,
+        vec![
+            (Some("countdown"), "countdown(2)"),
+            (Some("countdown"), "countdown(n - 1)"),
+            // The call to helper is a tail call, so it replaces the frame of the countdown call
+            // that made it
+            (Some("helper"), "helper(n)"),
+        ]
+    );
+
+    // Anonymous functions get frames without a name, and errors outside of any function don't
+    // have a backtrace
+    let error = expect_eval_error("fun() { foo }();");
+    assert_eq!(error.backtrace.len(), 1);
+    assert_eq!(error.backtrace[0].function, None);
+    assert!(expect_eval_error("foo;").backtrace.is_empty());
+}
+
+#[test]
+fn integer_overflow_errors() {
+    let tests = vec![
+        ("9223372036854775807 + 1;", "9223372036854775807 + 1"),
+        ("-9223372036854775807 - 2;", "-9223372036854775807 - 2"),
+        ("4611686018427387904 * 2;", "4611686018427387904 * 2"),
+        (
+            "(-9223372036854775807 - 1) / -1;",
+            "-9223372036854775808 / -1",
+        ),
+        ("-(-9223372036854775807 - 1);", "--9223372036854775808"),
+    ];
+    for (input, operation) in tests {
+        let error = expect_eval_error(input);
+        assert_eq!(
+            error.kind,
+            EvaluatorErrorKind::IntegerOverflow {
+                operation: operation.to_string()
+            }
+        );
+        assert_eq!(error.code(), "R0012");
+    }
+}
+
+#[test]
+fn integer_overflow_promotion() {
+    let tests = vec![
+        ("9223372036854775807 + 1;", "9223372036854775808"),
+        ("-9223372036854775807 - 2;", "-9223372036854775809"),
+        ("-(-9223372036854775807 - 1);", "9223372036854775808"),
+        (
+            "let fact = fun(n) { if (n == 0) { 1 } else { n * fact(n - 1) } }; fact(30);",
+            "265252859812191058636308480000000",
+        ),
+        // Results that fit in an i64 again are ordinary integers
+        ("(9223372036854775807 + 1) - 1;", "9223372036854775807"),
+        ("(9223372036854775807 * 4) / 4;", "9223372036854775807"),
+        ("9223372036854775807 * 3 / -9223372036854775807;", "-3"),
+        ("9223372036854775807 + 1 > 9223372036854775807;", "true"),
+        (
+            "9223372036854775807 + 1 == 9223372036854775807 + 1;",
+            "true",
+        ),
+        ("9223372036854775807 + 1 != 9223372036854775807;", "true"),
+        ("(9223372036854775807 + 1) * 0.5;", "4611686018427387904.0"),
+        (
+            "{9223372036854775807 + 1: 1, 1: 2};",
+            "{1: 2, 9223372036854775808: 1}",
+        ),
+        ("float(9223372036854775807 * 2);", "18446744073709551616.0"),
+    ];
+    for (input, expected) in tests {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        let mut evaluator = Evaluator::new();
+        evaluator.set_overflow_behavior(OverflowBehavior::Promote);
+        let evaluated = evaluator
+            .eval(&program, &mut Environment::new())
+            .expect("Evaluation failed");
+        assert_eq!(evaluated.inspect(), expected, "input: {input}");
+    }
+}
+
+#[test]
+fn interpolated_string_evaluation() {
+    let tests = vec![
+        (r#"let name = "Donkey"; "hello ${name}!";"#, "hello Donkey!"),
+        (
+            r#"let items = [1, "two"]; "${len(items)} items: ${items}";"#,
+            r#"2 items: [1, "two"]"#,
+        ),
+        (r#""${1 + 2} ${2.5} ${true} ${null}";"#, "3 2.5 true null"),
+        (r#""${"nested ${1 * 2}"}";"#, "nested 2"),
+        (r#""\${literal}";"#, "${literal}"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        let string = evaluated
+            .as_any()
+            .downcast_ref::<StringObject>()
+            .expect("Expected string");
+        assert_eq!(string.value, expected, "input: {input}");
+    }
+}
+
+#[test]
+fn string_operations() {
+    let tests = vec![
+        (r#""foo" + "bar";"#, r#""foobar""#),
+        (r#""a" + "b" + "c";"#, r#""abc""#),
+        (r#""abc" == "abc";"#, "true"),
+        (r#""abc" != "abc";"#, "false"),
+        (r#""abc" == "abd";"#, "false"),
+        (r#""abc" == 1;"#, "false"),
+        (r#""apple" < "banana";"#, "true"),
+        (r#""b" > "abc";"#, "true"),
+        (r#""abc" <= "abc";"#, "true"),
+        (r#""ab" >= "abc";"#, "false"),
+        (r#""héllo"[1];"#, r#""é""#),
+        (r#""héllo"[1:3];"#, r#""él""#),
+        (r#""hello"[:2];"#, r#""he""#),
+        (r#""hello"[2:];"#, r#""llo""#),
+        (r#""hello"[:];"#, r#""hello""#),
+        (r#""hello"[5:];"#, r#""""#),
+        ("[1, 2, 3, 4][1:3];", "[2, 3]"),
+        ("[1, 2, 3][:0];", "[]"),
+        (r#"len("héllo");"#, "5"),
+        (r#"len("");"#, "0"),
+        (r#"split("a,b,,c", ",");"#, r#"["a", "b", "", "c"]"#),
+        (r#"join(["a", "b", "c"], ", ");"#, r#""a, b, c""#),
+        (r#"join([], "-");"#, r#""""#),
+        (r#"trim("  hi \n");"#, r#""hi""#),
+        (r#"upper("Hi");"#, r#""HI""#),
+        (r#"lower("Hi");"#, r#""hi""#),
+        (r#"contains("donkey", "key");"#, "true"),
+        (r#"contains("donkey", "monkey");"#, "false"),
+        (r#"replace("a-b-c", "-", "+");"#, r#""a+b+c""#),
+        (r#"starts_with("donkey", "don");"#, "true"),
+        (r#"ends_with("donkey", "don");"#, "false"),
+        (r#"chars("hé");"#, r#"["h", "é"]"#),
+        ("to_string(12);", r#""12""#),
+        (r#"to_string([1, "a"]);"#, r#""[1, \"a\"]""#),
+        (r#"to_string("a");"#, r#""a""#),
+        (r#"parse_int("-42");"#, "-42"),
+        (r#"parse_int(to_string(7)) + 1;"#, "8"),
+    ];
+    for (input, expected) in tests {
+        let evaluated = test_eval(input);
+        assert_eq!(evaluated.inspect(), expected, "Input: {input}");
+    }
+
+    let tests = vec![
+        (
+            r#""a" + 1;"#,
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "string",
+                found: "integer",
+            },
+        ),
+        (
+            r#""a" - "b";"#,
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "string",
+            },
+        ),
+        (
+            r#""abc"[3];"#,
+            EvaluatorErrorKind::IndexOutOfBounds { index: 3, len: 3 },
+        ),
+        (
+            r#""abc"[2:1];"#,
+            EvaluatorErrorKind::InvalidSlice {
+                start: 2,
+                end: 1,
+                len: 3,
+            },
+        ),
+        (
+            "[1, 2][0:3];",
+            EvaluatorErrorKind::InvalidSlice {
+                start: 0,
+                end: 3,
+                len: 2,
+            },
+        ),
+        (
+            r#""abc"["a":];"#,
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "string",
+            },
+        ),
+        (
+            "5[0:1];",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "array or string",
+                found: "integer",
+            },
+        ),
+        (
+            "len(5);",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "array or string",
+                found: "integer",
+            },
+        ),
+        (
+            r#"join(["a", 1], "");"#,
+            EvaluatorErrorKind::InvalidArgument {
+                function: "join".to_string(),
+                reason: "Expected string, found integer at index [1]".to_string(),
+            },
+        ),
+        (
+            r#"split("abc", "");"#,
+            EvaluatorErrorKind::InvalidArgument {
+                function: "split".to_string(),
+                reason: "the separator can't be empty, use `chars` instead".to_string(),
+            },
+        ),
+        (
+            r#"parse_int("12a");"#,
+            EvaluatorErrorKind::InvalidArgument {
+                function: "parse_int".to_string(),
+                reason: r#""12a" is not a valid integer"#.to_string(),
+            },
+        ),
+    ];
+    for (input, expected_kind) in tests {
+        assert_eq!(
+            expect_eval_error(input).kind,
+            expected_kind,
+            "Input: {input}"
+        );
     }
+    assert_eq!(expect_eval_error(r#""abc"[2:1];"#).code(), "R0013");
 }
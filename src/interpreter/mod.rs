@@ -0,0 +1,236 @@
+mod tests;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::evaluator::environment::Environment;
+use crate::evaluator::{
+    Arguments, Arity, CancellationHandle, Evaluator, EvaluatorError, EvaluatorErrorKind,
+    OverflowBehavior,
+};
+use crate::lexer::Lexer;
+use crate::object::{BuiltinFn, Object};
+use crate::parser::{Parser, ParserError};
+use crate::token::Span;
+
+/// The errors that can happen while running Donkey code through an `Interpreter`.
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// The source file couldn't be read.
+    Io { path: PathBuf, error: io::Error },
+    /// The source code has syntax errors. Every error the parser found is included.
+    Parse(Vec<ParserError>),
+    /// Evaluating the program failed.
+    Runtime(EvaluatorError),
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::Io { path, error } => {
+                write!(f, "Error reading file '{}': {error}", path.display())
+            }
+            InterpreterError::Parse(errors) => {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                write!(f, "{}", errors.join("\n"))
+            }
+            InterpreterError::Runtime(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for InterpreterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InterpreterError::Io { error, .. } => Some(error),
+            InterpreterError::Parse(_) => None,
+            InterpreterError::Runtime(error) => Some(error),
+        }
+    }
+}
+
+impl From<EvaluatorError> for InterpreterError {
+    fn from(error: EvaluatorError) -> Self {
+        InterpreterError::Runtime(error)
+    }
+}
+
+/// Runs Donkey programs. This is the entry point for embedding Donkey in a Rust application.
+///
+/// Global bindings persist between calls, so a program can be fed to the interpreter piece by
+/// piece, and the functions it defines can be called from Rust afterwards:
+///
+/// ```
+/// use monkey_interpreter::Interpreter;
+/// use monkey_interpreter::object::{FromObject, IntoObject};
+///
+/// let mut interpreter = Interpreter::new();
+/// interpreter.eval_str("let double = fun(x) { x * 2 };").unwrap();
+/// let result = interpreter.call("double", vec![21.into_object()]).unwrap();
+/// assert_eq!(i64::from_object(result.as_ref()), Ok(42));
+/// ```
+pub struct Interpreter {
+    evaluator: Evaluator,
+    globals: Environment,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self {
+            evaluator: Evaluator::new(),
+            globals: Environment::new(),
+        }
+    }
+
+    /// Parses and evaluates `source`, returning the value of the last statement.
+    pub fn eval_str(&mut self, source: &str) -> Result<Box<dyn Object>, InterpreterError> {
+        let mut parser = Parser::new(Lexer::new(source));
+        let program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            return Err(InterpreterError::Parse(parser.errors().to_vec()));
+        }
+        Ok(self.evaluator.eval(&program, &mut self.globals)?)
+    }
+
+    /// Reads the file at `path` and evaluates it like `eval_str`.
+    pub fn eval_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Box<dyn Object>, InterpreterError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| InterpreterError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        self.eval_str(&source)
+    }
+
+    /// Returns the value of the global binding `name`, if there is one.
+    pub fn get_global(&self, name: &str) -> Option<Box<dyn Object>> {
+        self.globals.get(name)
+    }
+
+    /// Binds `name` to `value` in the global environment, replacing any existing binding.
+    pub fn set_global(&mut self, name: &str, value: Box<dyn Object>) {
+        self.globals.set(name, value);
+    }
+
+    /// Sets what happens when integer arithmetic overflows an `i64`. Overflow is an error by
+    /// default.
+    pub fn set_overflow_behavior(&mut self, overflow_behavior: OverflowBehavior) {
+        self.evaluator.set_overflow_behavior(overflow_behavior);
+    }
+
+    /// Sets how many Donkey function calls can be nested before evaluation fails with a
+    /// `RecursionLimitExceeded` error. The stack limit set by `set_max_stack_size` is usually
+    /// reached first, so raising the call depth alone rarely allows deeper recursion. See
+    /// `Evaluator::set_max_call_depth`.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.evaluator.set_max_call_depth(max_call_depth);
+    }
+
+    /// Sets how many bytes of native stack evaluation can use before it fails with a
+    /// `StackLimitExceeded` error. See `Evaluator::set_max_stack_size`.
+    pub fn set_max_stack_size(&mut self, max_stack_size: usize) {
+        self.evaluator.set_max_stack_size(max_stack_size);
+    }
+
+    /// Limits how many steps evaluation can take before it fails with an `ExecutionLimitExceeded`
+    /// error, or removes the limit if `step_limit` is `None`. Each call to `eval_str`, `eval_file`
+    /// or `call` gets the full number of steps. See `Evaluator::set_step_limit`.
+    pub fn set_step_limit(&mut self, step_limit: Option<u64>) {
+        self.evaluator.set_step_limit(step_limit);
+    }
+
+    /// Limits the approximate number of bytes that the objects created by Donkey code can take up
+    /// while they are alive, or removes the limit if `memory_limit` is `None`. See
+    /// `Evaluator::set_memory_limit`.
+    pub fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.evaluator.set_memory_limit(memory_limit);
+    }
+
+    /// Makes evaluation fail with a `Cancelled` error once `cancellation_handle` is cancelled,
+    /// which can be done from another thread:
+    ///
+    /// ```
+    /// use monkey_interpreter::evaluator::EvaluatorErrorKind;
+    /// use monkey_interpreter::{CancellationHandle, Interpreter, InterpreterError};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// let handle = CancellationHandle::new();
+    /// interpreter.set_cancellation_handle(handle.clone());
+    /// std::thread::spawn(move || handle.cancel());
+    /// let error = interpreter.eval_str("while (true) {}").err().unwrap();
+    /// assert!(matches!(
+    ///     error,
+    ///     InterpreterError::Runtime(error) if error.kind == EvaluatorErrorKind::Cancelled
+    /// ));
+    /// ```
+    pub fn set_cancellation_handle(&mut self, cancellation_handle: CancellationHandle) {
+        self.evaluator.set_cancellation_handle(cancellation_handle);
+    }
+
+    /// Makes the native function `function` callable from Donkey code as `name`. Calls with a
+    /// number of arguments not allowed by `arity` are rejected before `function` runs, and
+    /// `Arguments` has helpers for converting the arguments to Rust values:
+    ///
+    /// ```
+    /// use monkey_interpreter::object::IntoObject;
+    /// use monkey_interpreter::{Arity, Interpreter};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.register_function("repeat", Arity::Exact(2), |args| {
+    ///     let string = args.string(0)?;
+    ///     let count = args.integer(1)?;
+    ///     if count < 0 {
+    ///         return Err(args.invalid("count must not be negative"));
+    ///     }
+    ///     Ok(string.repeat(count as usize).into_object())
+    /// });
+    /// let result = interpreter.eval_str("repeat(\"ab\", 3);").unwrap();
+    /// assert_eq!(result.inspect(), "\"ababab\"");
+    /// ```
+    pub fn register_function<F>(&mut self, name: &str, arity: Arity, function: F)
+    where
+        F: Fn(Arguments) -> Result<Box<dyn Object>, EvaluatorError> + 'static,
+    {
+        self.evaluator
+            .register_builtin(BuiltinFn::new(name, arity, function));
+    }
+
+    /// Calls the global function `name` with `arguments` and returns its result.
+    pub fn call(
+        &mut self,
+        name: &str,
+        arguments: Vec<Box<dyn Object>>,
+    ) -> Result<Box<dyn Object>, InterpreterError> {
+        let Some(function) = self.get_global(name) else {
+            return Err(EvaluatorError::new(EvaluatorErrorKind::UnknownIdentifier {
+                name: name.to_string(),
+            })
+            .into());
+        };
+        // There is no call expression in the source for calls made from Rust, so the call site
+        // is left empty
+        Ok(self
+            .evaluator
+            .call_function(function.as_ref(), arguments, Span::default())?)
+    }
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        // Break the reference cycles between the global environment and the functions defined in
+        // it, so the functions and everything they captured are freed
+        self.globals.clear();
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
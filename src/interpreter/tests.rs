@@ -0,0 +1,232 @@
+#[cfg(test)]
+use crate::evaluator::{Arity, EvaluatorErrorKind};
+#[cfg(test)]
+use crate::interpreter::{Interpreter, InterpreterError};
+#[cfg(test)]
+use crate::object::{Integer, IntoObject, Object, StringObject};
+
+#[cfg(test)]
+fn expect_integer(obj: &dyn Object, expected: i64) {
+    match obj.as_any().downcast_ref::<Integer>() {
+        Some(integer) => assert_eq!(integer.value, expected),
+        None => panic!("Expected integer, got {}", obj.inspect()),
+    }
+}
+
+#[test]
+fn eval_str_keeps_globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval_str("let x = 5;").unwrap();
+    let result = interpreter.eval_str("x * 2;").unwrap();
+    expect_integer(result.as_ref(), 10);
+}
+
+#[test]
+fn get_and_set_globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("greeting", Box::new(StringObject::new("hi".to_string())));
+    interpreter.eval_str("let arr = [greeting, 2];").unwrap();
+    assert_eq!(
+        interpreter.get_global("arr").unwrap().inspect(),
+        "[\"hi\", 2]"
+    );
+    assert!(interpreter.get_global("missing").is_none());
+}
+
+#[test]
+fn closures_see_updated_globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("let scaled = fun(x) { x * factor };")
+        .unwrap();
+    interpreter.set_global("factor", Box::new(Integer::new(2)));
+    let result = interpreter.eval_str("scaled(5);").unwrap();
+    expect_integer(result.as_ref(), 10);
+    interpreter.set_global("factor", Box::new(Integer::new(3)));
+    let result = interpreter.eval_str("scaled(5);").unwrap();
+    expect_integer(result.as_ref(), 15);
+}
+
+#[test]
+fn call_functions_by_name() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str(
+            "let add = fun(a, b) { a + b };
+            let rec fib = fun(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } };",
+        )
+        .unwrap();
+    let result = interpreter
+        .call(
+            "add",
+            vec![Box::new(Integer::new(2)), Box::new(Integer::new(3))],
+        )
+        .unwrap();
+    expect_integer(result.as_ref(), 5);
+    let result = interpreter
+        .call("fib", vec![Box::new(Integer::new(10))])
+        .unwrap();
+    expect_integer(result.as_ref(), 55);
+    let result = interpreter
+        .call("len", vec![Box::new(StringObject::new("abc".to_string()))])
+        .map(|_| ());
+    assert!(matches!(result, Err(InterpreterError::Runtime(_))));
+}
+
+#[test]
+fn interpreter_errors() {
+    let mut interpreter = Interpreter::new();
+    let Err(InterpreterError::Parse(errors)) = interpreter.eval_str("let x = ;") else {
+        panic!("Expected a parse error");
+    };
+    assert!(!errors.is_empty());
+
+    let Err(InterpreterError::Runtime(error)) = interpreter.call("missing", Vec::new()) else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::UnknownIdentifier {
+            name: "missing".to_string()
+        }
+    );
+
+    interpreter.eval_str("let x = 1;").unwrap();
+    let Err(InterpreterError::Runtime(error)) = interpreter.call("x", Vec::new()) else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::NotCallable { found: "integer" }
+    );
+
+    let Err(InterpreterError::Io { .. }) = interpreter.eval_file("does/not/exist.donk") else {
+        panic!("Expected an io error");
+    };
+}
+
+#[test]
+fn eval_file() {
+    let mut interpreter = Interpreter::new();
+    let result = interpreter
+        .eval_file(concat!(env!("CARGO_MANIFEST_DIR"), "/sample/lists.donk"))
+        .map(|_| ());
+    assert!(result.is_ok());
+    assert!(interpreter.get_global("reverse").is_some());
+}
+
+#[test]
+fn register_host_functions() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_function("sum", Arity::AtLeast(1), |args| {
+        let mut total = 0;
+        for index in 0..args.len() {
+            total += args.integer(index)?;
+        }
+        Ok(Box::new(Integer::new(total)))
+    });
+    interpreter.register_function("shout", Arity::Exact(1), |args| {
+        Ok(Box::new(StringObject::new(args.string(0)?.to_uppercase())))
+    });
+
+    let result = interpreter.eval_str("sum(1, 2, 3) + sum(4);").unwrap();
+    expect_integer(result.as_ref(), 10);
+    // Host functions are values like any other builtin
+    let result = interpreter.eval_str("let f = shout; f(\"hi\");").unwrap();
+    assert_eq!(result.inspect(), "\"HI\"");
+
+    let tests = vec![
+        (
+            "sum();",
+            EvaluatorErrorKind::ArityMismatch {
+                function: Some("sum".to_string()),
+                expected: Arity::AtLeast(1),
+                found: 0,
+            },
+            "sum expects at least 1 argument(s), but 0 were given",
+        ),
+        (
+            "shout(\"a\", \"b\");",
+            EvaluatorErrorKind::ArityMismatch {
+                function: Some("shout".to_string()),
+                expected: Arity::Exact(1),
+                found: 2,
+            },
+            "shout expects 1 argument(s), but 2 were given",
+        ),
+        (
+            "sum(1, true);",
+            EvaluatorErrorKind::TypeMismatch {
+                expected: "integer",
+                found: "boolean",
+            },
+            "Expected integer, found boolean instead",
+        ),
+    ];
+    for (input, expected_kind, expected_message) in tests {
+        let Err(InterpreterError::Runtime(error)) = interpreter.eval_str(input) else {
+            panic!("Expected a runtime error for {input}");
+        };
+        assert_eq!(error.kind, expected_kind);
+        assert_eq!(error.kind.to_string(), expected_message);
+    }
+}
+
+#[test]
+fn registered_functions_replace_builtins() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_function("len", Arity::Exact(1), |_| Ok(Box::new(Integer::new(-1))));
+    let result = interpreter.eval_str("len([1, 2, 3]);").unwrap();
+    expect_integer(result.as_ref(), -1);
+}
+
+#[test]
+fn host_functions_convert_arguments() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_function("total", Arity::Exact(1), |args| {
+        let numbers: Vec<i64> = args.get_as(0)?;
+        Ok(numbers.iter().sum::<i64>().into_object())
+    });
+    let result = interpreter.eval_str("total([1, 2, 3]);").unwrap();
+    expect_integer(result.as_ref(), 6);
+
+    let Err(InterpreterError::Runtime(error)) = interpreter.eval_str("total([1, true]);") else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(
+        error.kind.to_string(),
+        "Invalid argument to total: Expected integer, found boolean at index [1]"
+    );
+    let Err(InterpreterError::Runtime(error)) = interpreter.eval_str("total(1);") else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::TypeMismatch {
+            expected: "array",
+            found: "integer"
+        }
+    );
+}
+
+#[test]
+fn step_limit_applies_to_each_evaluation() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_step_limit(Some(1000));
+    interpreter
+        .eval_str("let count = fun(n) { var i = 0; while (i < n) { i += 1; } i };")
+        .unwrap();
+    for _ in 0..3 {
+        let result = interpreter.eval_str("count(100);").unwrap();
+        expect_integer(result.as_ref(), 100);
+        let result = interpreter.call("count", vec![100.into_object()]).unwrap();
+        expect_integer(result.as_ref(), 100);
+    }
+    let Err(InterpreterError::Runtime(error)) = interpreter.eval_str("count(1000);") else {
+        panic!("Expected a runtime error");
+    };
+    assert_eq!(
+        error.kind,
+        EvaluatorErrorKind::ExecutionLimitExceeded { limit: 1000 }
+    );
+}
@@ -0,0 +1,70 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::token::Span;
+
+/// The different kinds of errors the lexer can report. The parser reports these along with its
+/// own errors, as `ParserErrorKind::Lexer`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LexerErrorKind {
+    /// A string literal that isn't closed before the end of the input.
+    UnterminatedString,
+    /// A backslash in a string literal that doesn't start a supported escape sequence.
+    /// `sequence` is the invalid sequence as written in the source.
+    InvalidEscapeSequence { sequence: String },
+}
+
+impl LexerErrorKind {
+    /// Returns the stable error code for this kind of error. Lexer error codes start with `L`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerErrorKind::UnterminatedString => "L0001",
+            LexerErrorKind::InvalidEscapeSequence { .. } => "L0002",
+        }
+    }
+
+    /// Returns a suggestion for fixing the error, if there is a useful one.
+    pub fn help(&self) -> Option<&'static str> {
+        match self {
+            LexerErrorKind::UnterminatedString => Some("add a `\"` to the end of the string"),
+            LexerErrorKind::InvalidEscapeSequence { .. } => Some(
+                "the supported escape sequences are \\n, \\t, \\\\, \\\", \\$ and \\u{...}, where ... is a hexadecimal code point",
+            ),
+        }
+    }
+}
+
+impl fmt::Display for LexerErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerErrorKind::UnterminatedString => write!(f, "Unterminated string literal"),
+            LexerErrorKind::InvalidEscapeSequence { sequence } => {
+                write!(f, "Invalid escape sequence in string literal: {sequence}")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LexerError {
+    pub kind: LexerErrorKind,
+    pub span: Span,
+}
+
+impl LexerError {
+    pub fn new(kind: LexerErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.start.line, self.span.start.column, self.kind
+        )
+    }
+}
+
+impl Error for LexerError {}
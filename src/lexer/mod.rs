@@ -1,37 +1,50 @@
+pub mod error;
+
 mod tests;
 
 use std::collections::HashMap;
+use std::mem;
+
+use crate::token::{Position, Span, Token, TokenType};
 
-use crate::token::{Token, TokenType};
+pub use error::{LexerError, LexerErrorKind};
 
 pub struct Lexer {
     input: Vec<char>,
+    // The source position of every character in `input`, plus one extra entry for the position
+    // just past the end of the input. Indexed the same way as `input`.
+    positions: Vec<Position>,
     cur_position: usize,
     // Always points to 1 ahead of `cur_position`
     read_position: usize,
     cur_char: char,
-    in_string: bool,
+    // The string literals whose interpolations are being lexed, innermost last. Each entry is the
+    // index of the literal's opening double quote and the number of braces opened in the
+    // interpolation that haven't been closed yet.
+    interpolations: Vec<(usize, usize)>,
+    errors: Vec<LexerError>,
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Self {
+        let input: Vec<char> = input.chars().collect();
+        let positions = Self::compute_positions(&input);
         let mut lexer = Self {
-            input: input.chars().collect(),
+            input,
+            positions,
             cur_position: 0,
             read_position: 0,
             cur_char: '\0',
-            in_string: false,
+            interpolations: Vec::new(),
+            errors: Vec::new(),
         };
         lexer.read_char();
         lexer
     }
 
     pub fn next_token(&mut self) -> Token {
-        if self.in_string && self.cur_char != '"' {
-            let value = self.read_til_double_quotation();
-            return Token::new(TokenType::StringValue, &value);
-        }
         self.skip_whitespace_and_comments();
+        let start = self.cur_position;
 
         let token = match self.cur_char {
             '=' => {
@@ -42,18 +55,45 @@ impl Lexer {
                     Token::new(TokenType::Assign, "=")
                 }
             }
-            '+' => Token::new(TokenType::Plus, "+"),
+            '+' => self.read_operator(TokenType::Plus, TokenType::PlusAssign),
             '(' => Token::new(TokenType::LParen, "("),
             ')' => Token::new(TokenType::RParen, ")"),
-            '{' => Token::new(TokenType::LBrace, "{"),
-            '}' => Token::new(TokenType::RBrace, "}"),
+            '{' => {
+                if let Some((_, open_braces)) = self.interpolations.last_mut() {
+                    *open_braces += 1;
+                }
+                Token::new(TokenType::LBrace, "{")
+            }
+            '}' => match self.interpolations.last_mut() {
+                // This brace closes an interpolation, so the string literal continues after it
+                Some((literal_start, 0)) => {
+                    let literal_start = *literal_start;
+                    self.interpolations.pop();
+                    let value = self.read_string(literal_start);
+                    if self.cur_char == '{' {
+                        Token::new(TokenType::StringMiddle, &value)
+                    } else {
+                        Token::new(TokenType::StringEnd, &value)
+                    }
+                }
+                Some((_, open_braces)) => {
+                    *open_braces -= 1;
+                    Token::new(TokenType::RBrace, "}")
+                }
+                None => Token::new(TokenType::RBrace, "}"),
+            },
             '[' => Token::new(TokenType::LSquare, "["),
             ']' => Token::new(TokenType::RSquare, "]"),
             '"' => {
-                self.in_string = !self.in_string;
-                Token::new(TokenType::DoubleQuotation, "\"")
+                let value = self.read_string(start);
+                if self.cur_char == '{' {
+                    Token::new(TokenType::StringStart, &value)
+                } else {
+                    Token::new(TokenType::StringValue, &value)
+                }
             }
             ',' => Token::new(TokenType::Comma, ","),
+            ':' => Token::new(TokenType::Colon, ":"),
             ';' => Token::new(TokenType::Semicolon, ";"),
             '!' => {
                 if self.peek_char() == '=' {
@@ -63,9 +103,26 @@ impl Lexer {
                     Token::new(TokenType::Bang, "!")
                 }
             }
-            '-' => Token::new(TokenType::Minus, "-"),
-            '/' => Token::new(TokenType::Slash, "/"),
-            '*' => Token::new(TokenType::Star, "*"),
+            '-' => self.read_operator(TokenType::Minus, TokenType::MinusAssign),
+            '/' => self.read_operator(TokenType::Slash, TokenType::SlashAssign),
+            '*' => self.read_operator(TokenType::Star, TokenType::StarAssign),
+            '%' => self.read_operator(TokenType::Percent, TokenType::PercentAssign),
+            '&' => {
+                if self.peek_char() == '&' {
+                    self.read_char(); // consume the second '&'
+                    Token::new(TokenType::LogicalAnd, "&&")
+                } else {
+                    Token::new(TokenType::Illegal, "&")
+                }
+            }
+            '|' => {
+                if self.peek_char() == '|' {
+                    self.read_char(); // consume the second '|'
+                    Token::new(TokenType::LogicalOr, "||")
+                } else {
+                    Token::new(TokenType::Illegal, "|")
+                }
+            }
             '<' => {
                 if self.peek_char() == '=' {
                     self.read_char(); // consume the second '='
@@ -82,7 +139,15 @@ impl Lexer {
                     Token::new(TokenType::RArrow, ">")
                 }
             }
-            '\0' => Token::new(TokenType::Eof, ""),
+            '\0' => {
+                for (literal_start, _) in mem::take(&mut self.interpolations) {
+                    self.errors.push(LexerError::new(
+                        LexerErrorKind::UnterminatedString,
+                        self.span_from(literal_start),
+                    ));
+                }
+                Token::new(TokenType::Eof, "")
+            }
             c => {
                 if c.is_alphabetic() || Self::is_underscore(c) {
                     let word = self.read_word();
@@ -94,25 +159,48 @@ impl Lexer {
                     let token_type = Self::lookup_ident(&word);
                     Token::new(token_type, &word)
                 } else if c.is_numeric() {
-                    let number = self.read_number();
+                    let (number, token_type) = self.read_number();
                     // Unread a character here because the functions used here (`read_word`, `read_number`)
                     // reads until the first character NOT in the literal. Then the `read_char` call below
                     // would then skip this character entirely, so we add a `unread_char` call here to
                     // not skip it.
                     self.unread_char();
-                    Token::new(TokenType::Int, &number)
+                    Token::new(token_type, &number)
                 } else {
                     Token::new(TokenType::Illegal, &c.to_string())
                 }
             }
         };
         self.read_char();
-        token
+        Token {
+            span: self.span_from(start),
+            ..token
+        }
+    }
+
+    // Reads an arithmetic operator, which is the compound assignment operator `assign` if it is
+    // followed by `=`
+    fn read_operator(&mut self, operator: TokenType, assign: TokenType) -> Token {
+        let c = self.cur_char;
+        if self.peek_char() == '=' {
+            self.read_char(); // consume the '='
+            Token::new(assign, &format!("{c}="))
+        } else {
+            Token::new(operator, &c.to_string())
+        }
+    }
+
+    /// Returns the errors found in the tokens returned so far, and forgets them so they are only
+    /// returned once.
+    pub fn take_errors(&mut self) -> Vec<LexerError> {
+        mem::take(&mut self.errors)
     }
 
     pub fn read_char(&mut self) {
         if self.read_position >= self.input.len() {
             self.cur_char = '\0';
+            self.cur_position = self.input.len();
+            self.read_position = self.input.len() + 1;
         } else {
             self.cur_char = self.input[self.read_position];
             self.cur_position = self.read_position;
@@ -140,6 +228,29 @@ impl Lexer {
         }
     }
 
+    /// Returns the span from the character at index `start` up to, but not including, the current
+    /// character.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(self.positions[start], self.positions[self.cur_position])
+    }
+
+    fn compute_positions(input: &[char]) -> Vec<Position> {
+        let mut positions = Vec::with_capacity(input.len() + 1);
+        let mut position = Position::new(1, 1, 0);
+        for c in input {
+            positions.push(position);
+            position.offset += c.len_utf8();
+            if *c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        positions.push(position);
+        positions
+    }
+
     fn read_word(&mut self) -> String {
         let mut word = String::new();
         while self.cur_char.is_alphanumeric() || Self::is_underscore(self.cur_char) {
@@ -151,13 +262,46 @@ impl Lexer {
 
     // Note: Even though this function is called `read_number`, it returns a String.
     // This is because in the lexing stage, all token literals are String types.
-    fn read_number(&mut self) -> String {
-        let mut number = String::new();
-        while self.cur_char.is_numeric() {
+    // Numbers with a fractional part (`3.14`) or an exponent (`1e-9`) are TokenType::Float, and
+    // other numbers are TokenType::Int.
+    fn read_number(&mut self) -> (String, TokenType) {
+        let mut number = self.read_digits();
+        let mut token_type = TokenType::Int;
+        // A dot is only part of the number if a digit follows it
+        if self.cur_char == '.' && self.peek_char().is_ascii_digit() {
             number.push(self.cur_char);
             self.read_char();
+            number.push_str(&self.read_digits());
+            token_type = TokenType::Float;
+        }
+        if matches!(self.cur_char, 'e' | 'E') {
+            let has_sign = matches!(self.peek_char(), '+' | '-');
+            let first_digit = if has_sign {
+                self.input.get(self.read_position + 1).copied()
+            } else {
+                Some(self.peek_char())
+            };
+            if first_digit.is_some_and(|c| c.is_ascii_digit()) {
+                number.push(self.cur_char);
+                self.read_char();
+                if has_sign {
+                    number.push(self.cur_char);
+                    self.read_char();
+                }
+                number.push_str(&self.read_digits());
+                token_type = TokenType::Float;
+            }
+        }
+        (number, token_type)
+    }
+
+    fn read_digits(&mut self) -> String {
+        let mut digits = String::new();
+        while self.cur_char.is_numeric() {
+            digits.push(self.cur_char);
+            self.read_char();
         }
-        number
+        digits
     }
 
     /// Returns TokenType::Ident if `word` is not a keyword in the Monkey
@@ -167,7 +311,7 @@ impl Lexer {
     ///
     /// Basic usage:
     ///
-    /// ```
+    /// ```ignore
     /// assert!(lookup_ident("let") == TokenType::Let)
     /// assert!(lookup_ident("fun") == TokenType::Function)
     /// assert!(lookup_ident("skibidi") == TokenType::Ident)
@@ -175,13 +319,20 @@ impl Lexer {
     fn lookup_ident(word: &str) -> TokenType {
         let mut keywords = HashMap::new();
         keywords.insert("let", TokenType::Let);
+        keywords.insert("var", TokenType::Var);
         keywords.insert("rec", TokenType::Rec);
+        keywords.insert("and", TokenType::And);
         keywords.insert("fun", TokenType::Function);
         keywords.insert("true", TokenType::True);
         keywords.insert("false", TokenType::False);
         keywords.insert("if", TokenType::If);
         keywords.insert("else", TokenType::Else);
         keywords.insert("return", TokenType::Return);
+        keywords.insert("while", TokenType::While);
+        keywords.insert("for", TokenType::For);
+        keywords.insert("in", TokenType::In);
+        keywords.insert("break", TokenType::Break);
+        keywords.insert("continue", TokenType::Continue);
         keywords.insert("null", TokenType::Null);
         *keywords.get(word).unwrap_or(&TokenType::Ident)
     }
@@ -206,20 +357,122 @@ impl Lexer {
         }
     }
 
-    // Advances characters until newline is encountered. This is used to handle comments
+    // Advances characters until newline or the end of the input is encountered. This is used to
+    // handle comments
     fn skip_til_newline(&mut self) {
-        while self.cur_char != '\n' {
+        while self.cur_char != '\n' && self.cur_char != '\0' {
             self.read_char();
         }
     }
 
-    // Advances characters until double quote is encountered. This is used to handle strings
-    fn read_til_double_quotation(&mut self) -> String {
+    // Reads the text of the string literal whose opening double quote is at index `literal_start`,
+    // starting after the current character (the opening double quote, or the `}` that closes an
+    // interpolation). Returns the text with escape sequences replaced by the characters they
+    // stand for. Stops at the closing double quote, at the `{` of an interpolation, or at the end
+    // of the input if the string is unterminated.
+    fn read_string(&mut self, literal_start: usize) -> String {
         let mut ret = String::new();
-        while self.cur_char != '"' {
-            ret.push(self.cur_char);
+        self.read_char(); // consume the '"' or '}'
+        loop {
+            match self.cur_char {
+                '"' => break,
+                '$' if self.peek_char() == '{' => {
+                    self.interpolations.push((literal_start, 0));
+                    self.read_char();
+                    break;
+                }
+                '\0' => {
+                    self.errors.push(LexerError::new(
+                        LexerErrorKind::UnterminatedString,
+                        self.span_from(literal_start),
+                    ));
+                    break;
+                }
+                '\\' => {
+                    // Invalid escape sequences are left out of the value, and lexing continues
+                    // after them so any other errors in the string are reported too
+                    if let Some(c) = self.read_escape_sequence() {
+                        ret.push(c);
+                    }
+                }
+                c => ret.push(c),
+            }
             self.read_char();
         }
         ret
     }
+
+    // Reads the escape sequence starting at the current backslash. Afterwards, the current
+    // character is the last character of the sequence.
+    fn read_escape_sequence(&mut self) -> Option<char> {
+        let start = self.cur_position;
+        self.read_char(); // consume the '\\'
+        let c = match self.cur_char {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            '\\' => Some('\\'),
+            '"' => Some('"'),
+            '$' => Some('$'),
+            'u' => self.read_unicode_escape(),
+            _ => None,
+        };
+        if c.is_none() {
+            let end = self.read_position.min(self.input.len());
+            let sequence = self.input[start..end].iter().collect();
+            self.errors.push(LexerError::new(
+                LexerErrorKind::InvalidEscapeSequence { sequence },
+                Span::new(self.positions[start], self.positions[end]),
+            ));
+        }
+        c
+    }
+
+    // Reads the `{...}` part of a `\u{...}` escape sequence, where `...` is between 1 and 6
+    // hexadecimal digits. The current character is the `u` when this is called.
+    fn read_unicode_escape(&mut self) -> Option<char> {
+        if self.peek_char() != '{' {
+            return None;
+        }
+        self.read_char();
+        let mut digits = String::new();
+        while self.peek_char().is_ascii_hexdigit() {
+            self.read_char();
+            digits.push(self.cur_char);
+        }
+        if self.peek_char() != '}' {
+            return None;
+        }
+        self.read_char();
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    /// Returns `value` written as a string literal, escaping the characters that can't appear in
+    /// one as is. This is the inverse of how the lexer reads string literals.
+    pub fn escape_string(value: &str) -> String {
+        format!("\"{}\"", Self::escape_string_text(value))
+    }
+
+    /// Like `escape_string`, but without the surrounding double quotes. Used for the text of
+    /// interpolated strings.
+    pub fn escape_string_text(value: &str) -> String {
+        let mut ret = String::new();
+        let mut chars = value.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => ret.push_str("\\n"),
+                '\t' => ret.push_str("\\t"),
+                '\\' => ret.push_str("\\\\"),
+                '"' => ret.push_str("\\\""),
+                '$' if chars.peek() == Some(&'{') => ret.push_str("\\$"),
+                c if c.is_control() => ret.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                c => ret.push(c),
+            }
+        }
+        ret
+    }
 }
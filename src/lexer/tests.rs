@@ -1,5 +1,5 @@
 #[cfg(test)]
-use crate::lexer::Lexer;
+use crate::lexer::{Lexer, LexerErrorKind};
 #[cfg(test)]
 use crate::token::TokenType;
 
@@ -31,6 +31,110 @@ fn test_next_token_simple() {
     }
 }
 
+#[test]
+fn test_next_token_logical_operators() {
+    let input = "a && b || c % 2 & |";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected_token_types = [
+        TokenType::Ident,
+        TokenType::LogicalAnd,
+        TokenType::Ident,
+        TokenType::LogicalOr,
+        TokenType::Ident,
+        TokenType::Percent,
+        TokenType::Int,
+        TokenType::Illegal,
+        TokenType::Illegal,
+        TokenType::Eof,
+    ];
+
+    let expected_token_literals = ["a", "&&", "b", "||", "c", "%", "2", "&", "|", ""];
+
+    for i in 0..expected_token_types.len() {
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, expected_token_types[i]);
+        assert_eq!(token.literal, expected_token_literals[i]);
+    }
+}
+
+#[test]
+fn test_next_token_assignment_operators() {
+    let input = "x = 1; x += 2; x -= 3; x *= 4; x /= 5; x %= 6; x == 7";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected_token_literals = [
+        "x", "=", "1", ";", "x", "+=", "2", ";", "x", "-=", "3", ";", "x", "*=", "4", ";", "x",
+        "/=", "5", ";", "x", "%=", "6", ";", "x", "==", "7", "",
+    ];
+    let expected_operator_types = [
+        TokenType::Assign,
+        TokenType::PlusAssign,
+        TokenType::MinusAssign,
+        TokenType::StarAssign,
+        TokenType::SlashAssign,
+        TokenType::PercentAssign,
+        TokenType::Eq,
+    ];
+
+    for (i, expected_literal) in expected_token_literals.iter().enumerate() {
+        let token = lexer.next_token();
+        assert_eq!(token.literal, *expected_literal);
+        if i % 4 == 1 {
+            assert_eq!(token.token_type, expected_operator_types[i / 4]);
+        }
+    }
+}
+
+#[test]
+fn test_next_token_loop_keywords() {
+    let input = "while for x in break continue inside";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected_token_types = [
+        TokenType::While,
+        TokenType::For,
+        TokenType::Ident,
+        TokenType::In,
+        TokenType::Break,
+        TokenType::Continue,
+        TokenType::Ident,
+        TokenType::Eof,
+    ];
+
+    for expected_token_type in expected_token_types {
+        assert_eq!(lexer.next_token().token_type, expected_token_type);
+    }
+}
+
+#[test]
+fn test_next_token_var_keyword() {
+    let input = "var x = 1; let variable = x;";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected_token_types = [
+        TokenType::Var,
+        TokenType::Ident,
+        TokenType::Assign,
+        TokenType::Int,
+        TokenType::Semicolon,
+        TokenType::Let,
+        TokenType::Ident,
+        TokenType::Assign,
+        TokenType::Ident,
+        TokenType::Semicolon,
+        TokenType::Eof,
+    ];
+
+    for expected_token_type in expected_token_types {
+        assert_eq!(lexer.next_token().token_type, expected_token_type);
+    }
+}
+
 #[test]
 fn test_next_token_skip_whitespace() {
     let input = " \t\n=\r\n +\t(\n )\r{  }\t,\n;\t ";
@@ -116,9 +220,7 @@ fn test_next_token_complex() {
         TokenType::Let,
         TokenType::Ident,
         TokenType::Assign,
-        TokenType::DoubleQuotation,
         TokenType::StringValue,
-        TokenType::DoubleQuotation,
         TokenType::Semicolon,
         TokenType::Let,
         TokenType::Ident,
@@ -183,11 +285,11 @@ fn test_next_token_complex() {
 
     let expected_token_literals = vec![
         "let", "five", "=", "5", ";", "let", "ten", "=", "10", ";", "let", "add", "=", "fun", "(",
-        "x", ",", "y", ")", "{", "x", "+", "y", ";", "}", ";", "let", "s", "=", "\"", "bob", "\"",
-        ";", "let", "result", "=", "add", "(", "five", ",", "ten", ")", ";", "!", "true", ";", "5",
-        "+", "12", "/", "10", "*", "2", ";", "5", "<", "105", ";", "10", "==", "10", ";", "10",
-        "!=", "9", ";", "5", "<=", "10", ";", "10", ">=", "5", ";", "if", "(", "5", "<", "10", ")",
-        "{", "return", "true", ";", "}", "else", "{", "return", "false", ";", "}",
+        "x", ",", "y", ")", "{", "x", "+", "y", ";", "}", ";", "let", "s", "=", "bob", ";", "let",
+        "result", "=", "add", "(", "five", ",", "ten", ")", ";", "!", "true", ";", "5", "+", "12",
+        "/", "10", "*", "2", ";", "5", "<", "105", ";", "10", "==", "10", ";", "10", "!=", "9",
+        ";", "5", "<=", "10", ";", "10", ">=", "5", ";", "if", "(", "5", "<", "10", ")", "{",
+        "return", "true", ";", "}", "else", "{", "return", "false", ";", "}",
         "", // Eof literal is an empty string
     ];
 
@@ -201,3 +303,219 @@ fn test_next_token_complex() {
         assert_eq!(token.literal, expected_token_literals[i]);
     }
 }
+
+#[test]
+fn test_next_token_spans() {
+    let input = "let x = 5;\n  foo(\"hé\") # comment\n";
+
+    let mut lexer = Lexer::new(input);
+
+    // (token type, (line, column, offset) of start, (line, column, offset) of end)
+    let expected = [
+        (TokenType::Let, (1, 1, 0), (1, 4, 3)),
+        (TokenType::Ident, (1, 5, 4), (1, 6, 5)),
+        (TokenType::Assign, (1, 7, 6), (1, 8, 7)),
+        (TokenType::Int, (1, 9, 8), (1, 10, 9)),
+        (TokenType::Semicolon, (1, 10, 9), (1, 11, 10)),
+        (TokenType::Ident, (2, 3, 13), (2, 6, 16)),
+        (TokenType::LParen, (2, 6, 16), (2, 7, 17)),
+        // The span of a string includes its quotes. 'é' is two bytes long in UTF-8, so the byte
+        // offset advances by 5 for 4 characters
+        (TokenType::StringValue, (2, 7, 17), (2, 11, 22)),
+        (TokenType::RParen, (2, 11, 22), (2, 12, 23)),
+        (TokenType::Eof, (3, 1, 34), (3, 1, 34)),
+    ];
+
+    for (token_type, start, end) in expected {
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, token_type);
+        assert_eq!(
+            (
+                token.span.start.line,
+                token.span.start.column,
+                token.span.start.offset
+            ),
+            start,
+            "Start mismatch for {token_type:?}"
+        );
+        assert_eq!(
+            (
+                token.span.end.line,
+                token.span.end.column,
+                token.span.end.offset
+            ),
+            end,
+            "End mismatch for {token_type:?}"
+        );
+    }
+}
+
+#[test]
+fn test_next_token_numbers() {
+    let input = "5 3.14 1e-9 2.5E+3 7e2 1.x 4e x1";
+
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = [
+        (TokenType::Int, "5"),
+        (TokenType::Float, "3.14"),
+        (TokenType::Float, "1e-9"),
+        (TokenType::Float, "2.5E+3"),
+        (TokenType::Float, "7e2"),
+        // A dot or an `e` that isn't followed by digits isn't part of the number
+        (TokenType::Int, "1"),
+        (TokenType::Illegal, "."),
+        (TokenType::Ident, "x"),
+        (TokenType::Int, "4"),
+        (TokenType::Ident, "e"),
+        (TokenType::Ident, "x1"),
+        (TokenType::Eof, ""),
+    ];
+
+    for (expected_type, expected_literal) in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, expected_type);
+        assert_eq!(token.literal, expected_literal);
+    }
+}
+
+#[test]
+fn test_next_token_string_escapes() {
+    let tests = [
+        (r#""a\nb""#, "a\nb"),
+        (r#""\tx\\y""#, "\tx\\y"),
+        (r#""say \"hi\"""#, "say \"hi\""),
+        (r#""\u{41}\u{1F600}\u{e9}""#, "A😀é"),
+        (r#""""#, ""),
+        ("\"multi\nline\"", "multi\nline"),
+    ];
+
+    for (input, expected) in tests {
+        let mut lexer = Lexer::new(input);
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::StringValue);
+        assert_eq!(token.literal, expected);
+        assert_eq!(lexer.next_token().token_type, TokenType::Eof);
+        assert!(lexer.take_errors().is_empty(), "input: {input}");
+    }
+}
+
+#[test]
+fn test_next_token_string_errors() {
+    // (input, literal of the string token, errors as (kind, start column, end column))
+    let tests = [
+        (
+            "\"abc",
+            "abc",
+            vec![(LexerErrorKind::UnterminatedString, 1, 5)],
+        ),
+        (
+            r#""a\qb""#,
+            "ab",
+            vec![(
+                LexerErrorKind::InvalidEscapeSequence {
+                    sequence: r"\q".to_string(),
+                },
+                3,
+                5,
+            )],
+        ),
+        (
+            r#""\u{110000}\u{zz}""#,
+            "zz}",
+            vec![
+                (
+                    LexerErrorKind::InvalidEscapeSequence {
+                        sequence: r"\u{110000}".to_string(),
+                    },
+                    2,
+                    12,
+                ),
+                (
+                    LexerErrorKind::InvalidEscapeSequence {
+                        sequence: r"\u{".to_string(),
+                    },
+                    12,
+                    15,
+                ),
+            ],
+        ),
+        (
+            r#""abc\"#,
+            "abc",
+            vec![
+                (
+                    LexerErrorKind::InvalidEscapeSequence {
+                        sequence: r"\".to_string(),
+                    },
+                    5,
+                    6,
+                ),
+                (LexerErrorKind::UnterminatedString, 1, 6),
+            ],
+        ),
+    ];
+
+    for (input, literal, expected_errors) in tests {
+        let mut lexer = Lexer::new(input);
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, TokenType::StringValue);
+        assert_eq!(token.literal, literal);
+        assert_eq!(lexer.next_token().token_type, TokenType::Eof);
+        let errors = lexer
+            .take_errors()
+            .into_iter()
+            .map(|error| (error.kind, error.span.start.column, error.span.end.column))
+            .collect::<Vec<_>>();
+        assert_eq!(errors, expected_errors, "input: {input}");
+    }
+}
+
+#[test]
+fn test_next_token_comment_at_end_of_input() {
+    let mut lexer = Lexer::new("1; # no newline after this comment");
+
+    assert_eq!(lexer.next_token().token_type, TokenType::Int);
+    assert_eq!(lexer.next_token().token_type, TokenType::Semicolon);
+    assert_eq!(lexer.next_token().token_type, TokenType::Eof);
+}
+
+#[test]
+fn test_next_token_interpolated_strings() {
+    let input = r#""a ${x} b ${f({1: "${y}"})} c" "\${d}""#;
+
+    let mut lexer = Lexer::new(input);
+
+    let expected = [
+        (TokenType::StringStart, "a "),
+        (TokenType::Ident, "x"),
+        (TokenType::StringMiddle, " b "),
+        (TokenType::Ident, "f"),
+        (TokenType::LParen, "("),
+        (TokenType::LBrace, "{"),
+        (TokenType::Int, "1"),
+        (TokenType::Colon, ":"),
+        (TokenType::StringStart, ""),
+        (TokenType::Ident, "y"),
+        (TokenType::StringEnd, ""),
+        (TokenType::RBrace, "}"),
+        (TokenType::RParen, ")"),
+        (TokenType::StringEnd, " c"),
+        (TokenType::StringValue, "${d}"),
+        (TokenType::Eof, ""),
+    ];
+
+    for (token_type, literal) in expected {
+        let token = lexer.next_token();
+        assert_eq!(token.token_type, token_type);
+        assert_eq!(token.literal, literal);
+    }
+    assert!(lexer.take_errors().is_empty());
+
+    let mut lexer = Lexer::new(r#""a ${x"#);
+    while lexer.next_token().token_type != TokenType::Eof {}
+    let errors = lexer.take_errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, LexerErrorKind::UnterminatedString);
+    assert_eq!(errors[0].span.start.column, 1);
+}
@@ -0,0 +1,16 @@
+//! An interpreter for Donkey, a small scripting language.
+//!
+//! `Interpreter` is the entry point for running Donkey code from Rust. The modules for each stage
+//! of the interpreter are public as well, for tools that need to work with tokens or the AST.
+
+pub mod ast;
+pub mod diagnostics;
+pub mod evaluator;
+pub mod interpreter;
+pub mod lexer;
+pub mod object;
+pub mod parser;
+pub mod token;
+
+pub use evaluator::{Arguments, Arity, CancellationHandle, OverflowBehavior};
+pub use interpreter::{Interpreter, InterpreterError};
@@ -1,20 +1,41 @@
-mod ast;
-mod evaluator;
-mod lexer;
-mod object;
-mod parser;
-mod token;
-
 use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
 use std::process;
+use std::thread;
+
+use monkey_interpreter::diagnostics::{Diagnostic, render_traceback};
+use monkey_interpreter::{Interpreter, InterpreterError};
+
+/// Prints a diagnostic to stderr, using colors only when stderr is a terminal and the user hasn't
+/// opted out through the NO_COLOR environment variable.
+fn report(diagnostic: Diagnostic, filename: &str, source: &str) {
+    if io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none() {
+        eprint!("{}", diagnostic.render_colored(filename, source));
+    } else {
+        eprint!("{}", diagnostic.render_plain(filename, source));
+    }
+}
 
-use evaluator::{Evaluator, environment::Environment};
-use lexer::Lexer;
-use parser::{Parser, has_parser_errors};
+/// Prints every error in `error` to stderr, with a traceback for runtime errors.
+fn report_error(error: &InterpreterError, filename: &str, source: &str) {
+    match error {
+        InterpreterError::Io { .. } => eprintln!("{error}"),
+        InterpreterError::Parse(errors) => {
+            for error in errors {
+                report(Diagnostic::from(error), filename, source);
+            }
+        }
+        InterpreterError::Runtime(error) => {
+            eprint!("{}", render_traceback(error, filename, source));
+            report(Diagnostic::from(error), filename, source);
+        }
+    }
+}
 
 fn execute_file(filename: &str) {
+    // The file is read here rather than through `Interpreter::eval_file` because the source is
+    // needed to render diagnostics
     let contents = match fs::read_to_string(filename) {
         Ok(contents) => contents,
         Err(error) => {
@@ -23,30 +44,17 @@ fn execute_file(filename: &str) {
         }
     };
 
-    let lexer = Lexer::new(&contents);
-    let mut parser = Parser::new(lexer);
-    let program = parser.parse_program();
-
-    if has_parser_errors(&parser) {
-        eprintln!("Parser errors found in file '{filename}'");
+    let mut interpreter = new_interpreter();
+    if let Err(error) = interpreter.eval_str(&contents) {
+        report_error(&error, filename, &contents);
         process::exit(1);
     }
-
-    let mut env = Environment::new();
-    let mut evaluator = Evaluator::new();
-    match evaluator.eval(&program, &mut env) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("Runtime error: {}", e.error_message);
-            process::exit(1);
-        }
-    }
 }
 
 fn run_repl() {
     println!("Welcome to the Monkey programming language!");
     println!("Press Ctrl+D to exit");
-    let mut env = Environment::new();
+    let mut interpreter = new_interpreter();
     loop {
         let mut input_string = String::new();
         print!(">>> ");
@@ -56,26 +64,12 @@ fn run_repl() {
                 println!("Exiting... Bye Bye!");
                 break;
             }
-            Ok(_) => {
-                let lexer = Lexer::new(&input_string);
-                let mut parser = Parser::new(lexer);
-                let program = parser.parse_program();
-                if !has_parser_errors(&parser) {
-                    // println!("Printing AST...");
-                    // for statement in &program.statements {
-                    //     println!("{}", statement.string());
-                    // }
-                    let mut evaluator = Evaluator::new();
-                    match evaluator.eval(&program, &mut env) {
-                        Ok(value) => {
-                            println!("{}", value.inspect());
-                        }
-                        Err(e) => {
-                            println!("Error evaluating program: {}", e.error_message)
-                        }
-                    }
+            Ok(_) => match interpreter.eval_str(&input_string) {
+                Ok(value) => {
+                    println!("{}", value.inspect());
                 }
-            }
+                Err(error) => report_error(&error, "<repl>", &input_string),
+            },
             Err(error) => {
                 eprintln!("Error reading input: {error}");
                 break;
@@ -84,12 +78,35 @@ fn run_repl() {
     }
 }
 
+// Every nested Donkey function call uses native stack space. The stack of the main thread only
+// allows a few hundred nested calls in debug builds, so Donkey code runs on a thread with a bigger
+// one that fits the interpreter's default recursion limit.
+const INTERPRETER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// How much of the interpreter thread's stack evaluation can use. The rest is left for the REPL and
+// for reporting errors.
+const MAX_EVALUATION_STACK_SIZE: usize = 56 * 1024 * 1024;
+
+fn new_interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_stack_size(MAX_EVALUATION_STACK_SIZE);
+    interpreter
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() > 1 {
-        execute_file(&args[1]);
-    } else {
-        run_repl();
+    let interpreter_thread = thread::Builder::new()
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(move || {
+            if args.len() > 1 {
+                execute_file(&args[1]);
+            } else {
+                run_repl();
+            }
+        })
+        .expect("Failed to start the interpreter thread");
+    if interpreter_thread.join().is_err() {
+        process::exit(101);
     }
 }
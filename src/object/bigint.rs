@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Digits are stored in base 10^9 so that printing a number doesn't need any division, and so the
+// product of two digits plus a carry fits in a u64
+const BASE: u64 = 1_000_000_000;
+const BASE_DIGITS: usize = 9;
+
+/// An integer of any size, used when arithmetic on Donkey integers overflows an `i64`.
+///
+/// The representation is canonical (no leading zero digits, and zero is never negative), so the
+/// derived equality and hashing compare values.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BigInt {
+    negative: bool,
+    // The digits of the absolute value in base 10^9, least significant digit first. Zero has no
+    // digits.
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Self {
+        trim(&mut digits);
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    /// Returns the number of bytes the digits of the number take up.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.digits.len() * std::mem::size_of::<u32>()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// Returns the value as an `i64`, or `None` if it doesn't fit in one.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 3 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0u128, |acc, digit| acc * BASE as u128 + *digit as u128);
+        if self.negative {
+            i64::try_from(-(magnitude as i128)).ok()
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// Returns the nearest float to the value. Values too large for an `f64` become infinite.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, digit| acc * BASE as f64 + *digit as f64);
+        if self.negative { -magnitude } else { magnitude }
+    }
+
+    /// Divides `self` by `divisor`, rounding towards zero like integer division on `i64`.
+    /// Returns `None` if `divisor` is zero.
+    pub fn checked_div(&self, divisor: &BigInt) -> Option<BigInt> {
+        if divisor.is_zero() {
+            return None;
+        }
+        // Schoolbook long division, finding each digit of the quotient with a binary search
+        let mut quotient = vec![0; self.digits.len()];
+        let mut remainder = Vec::new();
+        for (i, digit) in self.digits.iter().enumerate().rev() {
+            remainder.insert(0, *digit);
+            trim(&mut remainder);
+            let (mut low, mut high) = (0, BASE as u32 - 1);
+            while low < high {
+                let mid = low + (high - low).div_ceil(2);
+                if compare_digits(&multiply_digits(&divisor.digits, &[mid]), &remainder)
+                    == Ordering::Greater
+                {
+                    high = mid - 1;
+                } else {
+                    low = mid;
+                }
+            }
+            remainder = subtract_digits(&remainder, &multiply_digits(&divisor.digits, &[low]));
+            quotient[i] = low;
+        }
+        Some(BigInt::from_parts(
+            self.negative != divisor.negative,
+            quotient,
+        ))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = Vec::new();
+        while magnitude > 0 {
+            digits.push((magnitude % BASE) as u32);
+            magnitude /= BASE;
+        }
+        BigInt::from_parts(value < 0, digits)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_digits(&self.digits, &other.digits),
+            (true, true) => compare_digits(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.digits.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(self.negative, add_digits(&self.digits, &other.digits));
+        }
+        // The signs differ, so the result has the sign of the operand with the larger magnitude
+        match compare_digits(&self.digits, &other.digits) {
+            Ordering::Less => {
+                BigInt::from_parts(other.negative, subtract_digits(&other.digits, &self.digits))
+            }
+            _ => BigInt::from_parts(self.negative, subtract_digits(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            multiply_digits(&self.digits, &other.digits),
+        )
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((most_significant, rest)) = self.digits.split_last() else {
+            return write!(f, "0");
+        };
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{most_significant}")?;
+        for digit in rest.iter().rev() {
+            write!(f, "{digit:0BASE_DIGITS$}")?;
+        }
+        Ok(())
+    }
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn compare_digits(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0;
+    for i in 0..left.len().max(right.len()) {
+        let total = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push((total % BASE) as u32);
+        carry = total / BASE;
+    }
+    sum.push(carry as u32);
+    trim(&mut sum);
+    sum
+}
+
+// Subtracts `right` from `left`, which must have the larger magnitude
+fn subtract_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(left.len());
+    let mut borrow = 0;
+    for (i, digit) in left.iter().enumerate() {
+        let subtrahend = *right.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut total = *digit as i64 - subtrahend;
+        borrow = 0;
+        if total < 0 {
+            total += BASE as i64;
+            borrow = 1;
+        }
+        difference.push(total as u32);
+    }
+    trim(&mut difference);
+    difference
+}
+
+fn multiply_digits(left: &[u32], right: &[u32]) -> Vec<u32> {
+    if left.is_empty() || right.is_empty() {
+        return Vec::new();
+    }
+    let mut product = vec![0u64; left.len() + right.len()];
+    for (i, left_digit) in left.iter().enumerate() {
+        let mut carry = 0;
+        for (j, right_digit) in right.iter().enumerate() {
+            let total = product[i + j] + *left_digit as u64 * *right_digit as u64 + carry;
+            product[i + j] = total % BASE;
+            carry = total / BASE;
+        }
+        product[i + right.len()] += carry;
+    }
+    let mut product = product.into_iter().map(|digit| digit as u32).collect();
+    trim(&mut product);
+    product
+}
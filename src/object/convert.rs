@@ -0,0 +1,232 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::evaluator::{EvaluatorError, EvaluatorErrorKind};
+use crate::object::{
+    Array, BigInt, BigInteger, Boolean, Float, Integer, Null, Object, StringObject,
+};
+
+/// Conversion of a Rust value into a Donkey object.
+pub trait IntoObject {
+    fn into_object(self) -> Box<dyn Object>;
+}
+
+/// Conversion of a Donkey object into a Rust value. Fails with a `ConversionError` if the object
+/// doesn't have the right type.
+pub trait FromObject: Sized {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError>;
+}
+
+/// A Donkey object couldn't be converted to a Rust value because it had the wrong type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+    // The array indices leading from the converted object to the value that had the wrong type,
+    // outermost index first. This is empty if the converted object itself had the wrong type.
+    pub path: Vec<usize>,
+}
+
+impl ConversionError {
+    pub fn new(expected: &'static str, found: &dyn Object) -> Self {
+        Self {
+            expected,
+            found: found.type_name(),
+            path: Vec::new(),
+        }
+    }
+
+    // Records that the error happened in the item at `index` of an array
+    fn in_item(mut self, index: usize) -> Self {
+        self.path.insert(0, index);
+        self
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expected {}, found {}", self.expected, self.found)?;
+        if !self.path.is_empty() {
+            let path: String = self.path.iter().map(|index| format!("[{index}]")).collect();
+            write!(f, " at index {path}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConversionError {}
+
+impl From<ConversionError> for EvaluatorError {
+    fn from(error: ConversionError) -> Self {
+        EvaluatorError::new(EvaluatorErrorKind::TypeMismatch {
+            expected: error.expected,
+            found: error.found,
+        })
+    }
+}
+
+impl IntoObject for Box<dyn Object> {
+    fn into_object(self) -> Box<dyn Object> {
+        self
+    }
+}
+
+impl FromObject for Box<dyn Object> {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        Ok(dyn_clone::clone_box(object))
+    }
+}
+
+impl IntoObject for i64 {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(Integer::new(self))
+    }
+}
+
+impl FromObject for i64 {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        match object.as_any().downcast_ref::<Integer>() {
+            Some(integer) => Ok(integer.value),
+            None => Err(ConversionError::new("integer", object)),
+        }
+    }
+}
+
+impl IntoObject for f64 {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(Float::new(self))
+    }
+}
+
+/// Integers are converted to floats too, since Donkey code mixes them freely.
+impl FromObject for f64 {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        if let Some(float) = object.as_any().downcast_ref::<Float>() {
+            Ok(float.value)
+        } else if let Some(integer) = object.as_any().downcast_ref::<Integer>() {
+            Ok(integer.value as f64)
+        } else if let Some(big_integer) = object.as_any().downcast_ref::<BigInteger>() {
+            Ok(big_integer.value.to_f64())
+        } else {
+            Err(ConversionError::new("float", object))
+        }
+    }
+}
+
+/// Values that fit in an `i64` become an `Integer`, so they compare equal to other integers.
+impl IntoObject for BigInt {
+    fn into_object(self) -> Box<dyn Object> {
+        BigInteger::normalized(self)
+    }
+}
+
+/// Accepts integers of any size.
+impl FromObject for BigInt {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        if let Some(integer) = object.as_any().downcast_ref::<Integer>() {
+            Ok(BigInt::from(integer.value))
+        } else if let Some(big_integer) = object.as_any().downcast_ref::<BigInteger>() {
+            Ok(big_integer.value.clone())
+        } else {
+            Err(ConversionError::new("integer", object))
+        }
+    }
+}
+
+impl IntoObject for bool {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(Boolean::new(self))
+    }
+}
+
+impl FromObject for bool {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        match object.as_any().downcast_ref::<Boolean>() {
+            Some(boolean) => Ok(boolean.value),
+            None => Err(ConversionError::new("boolean", object)),
+        }
+    }
+}
+
+impl IntoObject for String {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(StringObject::new(self))
+    }
+}
+
+impl IntoObject for &str {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(StringObject::new(self.to_string()))
+    }
+}
+
+impl FromObject for String {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        match object.as_any().downcast_ref::<StringObject>() {
+            Some(string) => Ok(string.value.clone()),
+            None => Err(ConversionError::new("string", object)),
+        }
+    }
+}
+
+impl<T: IntoObject> IntoObject for Vec<T> {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(Array::new(
+            self.into_iter().map(IntoObject::into_object).collect(),
+        ))
+    }
+}
+
+impl<T: FromObject> FromObject for Vec<T> {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        match object.as_any().downcast_ref::<Array>() {
+            Some(array) => array
+                .items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    T::from_object(item.as_ref()).map_err(|error| error.in_item(index))
+                })
+                .collect(),
+            None => Err(ConversionError::new("array", object)),
+        }
+    }
+}
+
+/// `None` is converted to `null`.
+impl<T: IntoObject> IntoObject for Option<T> {
+    fn into_object(self) -> Box<dyn Object> {
+        match self {
+            Some(value) => value.into_object(),
+            None => Box::new(Null::new()),
+        }
+    }
+}
+
+/// `null` is converted to `None`, and any other object is converted to `T`.
+impl<T: FromObject> FromObject for Option<T> {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        if object.as_any().is::<Null>() {
+            Ok(None)
+        } else {
+            T::from_object(object).map(Some)
+        }
+    }
+}
+
+/// Unit is converted to `null`, which is what Donkey functions without a meaningful result return.
+impl IntoObject for () {
+    fn into_object(self) -> Box<dyn Object> {
+        Box::new(Null::new())
+    }
+}
+
+impl FromObject for () {
+    fn from_object(object: &dyn Object) -> Result<Self, ConversionError> {
+        if object.as_any().is::<Null>() {
+            Ok(())
+        } else {
+            Err(ConversionError::new("null", object))
+        }
+    }
+}
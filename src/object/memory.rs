@@ -0,0 +1,138 @@
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::rc::Rc;
+
+use crate::evaluator::environment;
+use crate::evaluator::{EvaluatorError, EvaluatorErrorKind};
+use crate::object::{Object, StringObject};
+
+// Sizes are approximate. Objects are charged for the memory they own directly, like the items of
+// an array or the bytes of a string, but not for allocator overhead or spare capacity. Numbers,
+// booleans and null don't own any memory, so they are only charged as part of the collections and
+// environments that hold them.
+
+thread_local! {
+    // The usage that objects created on this thread are charged to, if any
+    static TRACKED_USAGE: RefCell<Option<MemoryUsage>> = const { RefCell::new(None) };
+}
+
+/// A running total of the approximate number of bytes used by the objects and environments that
+/// were created while it was tracked, and that are still alive, along with the limit on it. Clones
+/// share the same total and limit.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MemoryUsage {
+    bytes: Rc<Cell<usize>>,
+    limit: Rc<Cell<Option<usize>>>,
+}
+
+impl MemoryUsage {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+
+    pub(crate) fn limit(&self) -> Option<usize> {
+        self.limit.get()
+    }
+
+    pub(crate) fn set_limit(&self, limit: Option<usize>) {
+        self.limit.set(limit);
+    }
+
+    /// Fails with a `MemoryLimitExceeded` error if `additional` more bytes would take the usage
+    /// over its limit. Scopes that are only kept alive by reference cycles still count until they
+    /// are collected, so they are collected before giving up.
+    pub(crate) fn check(&self, additional: usize) -> Result<(), EvaluatorError> {
+        let Some(limit) = self.limit() else {
+            return Ok(());
+        };
+        if self.bytes().saturating_add(additional) <= limit {
+            return Ok(());
+        }
+        environment::collect_cycles();
+        if self.bytes().saturating_add(additional) <= limit {
+            Ok(())
+        } else {
+            Err(EvaluatorError::new(
+                EvaluatorErrorKind::MemoryLimitExceeded { limit },
+            ))
+        }
+    }
+
+    /// Charges the objects created on this thread to this usage until the returned guard is
+    /// dropped.
+    pub(crate) fn track(&self) -> TrackingGuard {
+        let previous = TRACKED_USAGE.with(|tracked| tracked.replace(Some(self.clone())));
+        TrackingGuard { previous }
+    }
+}
+
+/// Checks that `bytes` more can be allocated without going over the limit of the usage that is
+/// tracked, if any. Operations whose results can be much bigger than their operands call this
+/// before making them, so that they fail before allocating far past the limit.
+pub(crate) fn reserve(bytes: usize) -> Result<(), EvaluatorError> {
+    let usage = TRACKED_USAGE.with(|tracked| tracked.borrow().clone());
+    match usage {
+        Some(usage) => usage.check(bytes),
+        None => Ok(()),
+    }
+}
+
+/// Restores the usage that was tracked before `MemoryUsage::track` was called when dropped.
+pub(crate) struct TrackingGuard {
+    previous: Option<MemoryUsage>,
+}
+
+impl Drop for TrackingGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        TRACKED_USAGE.with(|tracked| *tracked.borrow_mut() = previous);
+    }
+}
+
+/// The memory owned by an object. It's charged to the usage that was tracked when it was created
+/// for as long as it is alive. Cloning an allocation charges the copy to the usage that is tracked
+/// at the time, since the copy is new memory.
+#[derive(Debug)]
+pub(crate) struct Allocation {
+    usage: Option<MemoryUsage>,
+    bytes: usize,
+}
+
+impl Allocation {
+    pub(crate) fn new(bytes: usize) -> Self {
+        let usage = TRACKED_USAGE.with(|tracked| tracked.borrow().clone());
+        if let Some(usage) = &usage {
+            usage.bytes.set(usage.bytes() + bytes);
+        }
+        Self { usage, bytes }
+    }
+}
+
+impl Clone for Allocation {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes)
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        if let Some(usage) = &self.usage {
+            usage.bytes.set(usage.bytes() - self.bytes);
+        }
+    }
+}
+
+/// Returns the memory taken by a boxed object that a collection holds: the pointer to the object
+/// and the object itself. Memory owned by the object is charged to the object's own allocation.
+pub(crate) fn boxed_size(object: &dyn Object) -> usize {
+    mem::size_of::<Box<dyn Object>>() + mem::size_of_val(object)
+}
+
+/// Returns the memory taken by an array of `count` strings that are `bytes` long in total.
+pub(crate) fn string_array_size(count: usize, bytes: usize) -> usize {
+    count * (mem::size_of::<Box<dyn Object>>() + mem::size_of::<StringObject>()) + bytes
+}
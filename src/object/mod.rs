@@ -1,15 +1,75 @@
+pub mod bigint;
+pub mod convert;
+pub(crate) mod memory;
+
+mod tests;
+
 use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::mem;
 use std::rc::Rc;
 
 use dyn_clone::DynClone;
 
 use crate::ast::{BlockStatement, Identifier, Node};
 use crate::evaluator::EvaluatorError;
+use crate::evaluator::builtins::{Arguments, Arity};
 use crate::evaluator::environment::Environment;
+use crate::lexer::Lexer;
+use crate::object::memory::{Allocation, boxed_size};
+use crate::token::Span;
+
+pub use bigint::BigInt;
+pub use convert::{ConversionError, FromObject, IntoObject};
 
 pub trait Object: Any + DynClone {
     fn as_any(&self) -> &dyn Any;
     fn inspect(&self) -> String;
+    /// The object as shown by `print` and in interpolated strings. This is the same as `inspect`,
+    /// except that strings are shown without quotes or escapes.
+    fn display(&self) -> String {
+        self.inspect()
+    }
+    /// The name of the object's type, as shown to users in error messages.
+    fn type_name(&self) -> &'static str;
+    /// The key the object is stored under when it is used as a key in a hash, or `None` if
+    /// objects of this type can't be used as keys.
+    fn hash_key(&self) -> Option<HashKey> {
+        None
+    }
+    /// Compares the object with `other`. All objects are totally ordered, first by kind:
+    ///
+    /// 1. `null`
+    /// 2. Booleans, with `false` before `true`
+    /// 3. Numbers. Integers and floats are ordered by their values, and NaN comes after every
+    ///    other number
+    /// 4. Strings, ordered lexicographically by character
+    /// 5. Arrays, ordered lexicographically by their items
+    /// 6. Hashes, ordered lexicographically by their pairs in iteration order, comparing keys
+    ///    before values
+    /// 7. Ranges, ordered by their start and then by their end
+    /// 8. Functions, ordered by their source text. Functions with the same source text are only
+    ///    equal if they were created in the same scope. Builtin functions are ordered by name,
+    ///    after Donkey functions
+    /// 9. Any other objects, ordered by `type_name`
+    ///
+    /// The default implementation only orders objects by kind, so objects of other types with
+    /// the same type name compare equal unless the type overrides this.
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        compare_kinds(self.as_any(), other.as_any())
+            .then_with(|| self.type_name().cmp(other.type_name()))
+    }
+    /// Whether the object is equal to `other`, which is what `==` checks. This is the same as
+    /// `compare` returning `Ordering::Equal`, except that NaN isn't equal to anything.
+    fn equals(&self, other: &dyn Object) -> bool {
+        self.compare(other) == Ordering::Equal
+    }
+    /// Calls `visit` with every environment that the object holds on to, including the ones held
+    /// by the objects it contains. This is how the evaluator finds the reference cycles between
+    /// closures and the scopes they are stored in, so types that hold environments other than
+    /// through the objects here should override it.
+    fn visit_environments(&self, _visit: &mut dyn FnMut(&Environment)) {}
 }
 
 dyn_clone::clone_trait_object!(Object);
@@ -26,6 +86,22 @@ impl Object for Integer {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "integer"
+    }
+
+    fn hash_key(&self) -> Option<HashKey> {
+        Some(HashKey::Integer(self.value))
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Integer>() {
+            Some(other) => self.value.cmp(&other.value),
+            None => compare_numbers(self.as_any(), other.as_any())
+                .unwrap_or_else(|| compare_kinds(self.as_any(), other.as_any())),
+        }
+    }
+
     fn inspect(&self) -> String {
         self.value.to_string()
     }
@@ -39,6 +115,102 @@ impl Integer {
 
 // ========== Integer End ==========
 
+// ========== BigInteger Start ==========
+
+/// An integer outside the range of an `i64`. These are only created when integer arithmetic
+/// overflows and the evaluator is set to promote overflowing results, and arithmetic on them
+/// returns an `Integer` again whenever the result fits in one.
+#[derive(Clone)]
+pub struct BigInteger {
+    pub value: BigInt,
+    _allocation: Allocation,
+}
+
+impl Object for BigInteger {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "integer"
+    }
+
+    fn hash_key(&self) -> Option<HashKey> {
+        Some(HashKey::BigInteger(self.value.clone()))
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        compare_numbers(self.as_any(), other.as_any())
+            .unwrap_or_else(|| compare_kinds(self.as_any(), other.as_any()))
+    }
+
+    fn inspect(&self) -> String {
+        self.value.to_string()
+    }
+}
+
+impl BigInteger {
+    pub fn new(value: BigInt) -> Self {
+        Self {
+            _allocation: Allocation::new(value.heap_size()),
+            value,
+        }
+    }
+
+    /// Returns `value` as an `Integer` if it fits in an `i64`, or as a `BigInteger` otherwise.
+    pub fn normalized(value: BigInt) -> Box<dyn Object> {
+        match value.to_i64() {
+            Some(value) => Box::new(Integer::new(value)),
+            None => Box::new(Self::new(value)),
+        }
+    }
+}
+
+// ========== BigInteger End ==========
+
+// ========== Float Start ==========
+
+#[derive(Clone)]
+pub struct Float {
+    pub value: f64,
+}
+
+impl Object for Float {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "float"
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        compare_numbers(self.as_any(), other.as_any())
+            .unwrap_or_else(|| compare_kinds(self.as_any(), other.as_any()))
+    }
+
+    fn equals(&self, other: &dyn Object) -> bool {
+        !self.value.is_nan() && self.compare(other) == Ordering::Equal
+    }
+
+    fn inspect(&self) -> String {
+        // Whole floats keep their decimal point so they can be told apart from integers
+        if self.value.is_finite() && self.value.fract() == 0.0 {
+            format!("{:.1}", self.value)
+        } else {
+            self.value.to_string()
+        }
+    }
+}
+
+impl Float {
+    pub fn new(value: f64) -> Self {
+        Self { value }
+    }
+}
+
+// ========== Float End ==========
+
 // ========== Boolean Start ==========
 
 #[derive(Clone)]
@@ -51,6 +223,21 @@ impl Object for Boolean {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "boolean"
+    }
+
+    fn hash_key(&self) -> Option<HashKey> {
+        Some(HashKey::Boolean(self.value))
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Boolean>() {
+            Some(other) => self.value.cmp(&other.value),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
     fn inspect(&self) -> String {
         self.value.to_string()
     }
@@ -69,6 +256,7 @@ impl Boolean {
 #[derive(Clone)]
 pub struct Array {
     pub items: Vec<Box<dyn Object>>,
+    _allocation: Allocation,
 }
 
 impl Object for Array {
@@ -76,6 +264,41 @@ impl Object for Array {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "array"
+    }
+
+    fn visit_environments(&self, visit: &mut dyn FnMut(&Environment)) {
+        for item in &self.items {
+            item.visit_environments(visit);
+        }
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Array>() {
+            Some(other) => {
+                compare_sequences(self.items.iter(), other.items.iter(), |left, right| {
+                    left.compare(right.as_ref())
+                })
+            }
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
+    fn equals(&self, other: &dyn Object) -> bool {
+        match other.as_any().downcast_ref::<Array>() {
+            Some(other) => {
+                self.items.len() == other.items.len()
+                    && self
+                        .items
+                        .iter()
+                        .zip(&other.items)
+                        .all(|(left, right)| left.equals(right.as_ref()))
+            }
+            None => false,
+        }
+    }
+
     fn inspect(&self) -> String {
         let items_string = self
             .items
@@ -89,17 +312,159 @@ impl Object for Array {
 
 impl Array {
     pub fn new(items: Vec<Box<dyn Object>>) -> Self {
-        Self { items }
+        let bytes = items.iter().map(|item| boxed_size(item.as_ref())).sum();
+        Self {
+            items,
+            _allocation: Allocation::new(bytes),
+        }
     }
 }
 
 // ========== Array End ==========
 
+// ========== Hash Start ==========
+
+/// A value that can be used as a key in a hash. Keys are ordered by type (booleans, then
+/// integers, then strings) and then by value, which is the order hashes iterate in.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum HashKey {
+    Boolean(bool),
+    Integer(i64),
+    // Only used for integers that don't fit in an `i64`, so a big integer key is never equal to
+    // an `Integer` key
+    BigInteger(BigInt),
+    String(String),
+}
+
+impl HashKey {
+    /// Returns the object the key was created from.
+    pub fn to_object(&self) -> Box<dyn Object> {
+        match self {
+            HashKey::Boolean(value) => Box::new(Boolean::new(*value)),
+            HashKey::Integer(value) => Box::new(Integer::new(*value)),
+            HashKey::BigInteger(value) => Box::new(BigInteger::new(value.clone())),
+            HashKey::String(value) => Box::new(StringObject::new(value.clone())),
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            HashKey::Boolean(_) => 0,
+            HashKey::Integer(_) | HashKey::BigInteger(_) => 1,
+            HashKey::String(_) => 2,
+        }
+    }
+}
+
+impl Ord for HashKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (HashKey::Boolean(left), HashKey::Boolean(right)) => left.cmp(right),
+            (HashKey::Integer(left), HashKey::Integer(right)) => left.cmp(right),
+            (HashKey::BigInteger(left), HashKey::BigInteger(right)) => left.cmp(right),
+            (HashKey::Integer(left), HashKey::BigInteger(right)) => BigInt::from(*left).cmp(right),
+            (HashKey::BigInteger(left), HashKey::Integer(right)) => left.cmp(&BigInt::from(*right)),
+            (HashKey::String(left), HashKey::String(right)) => left.cmp(right),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for HashKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone)]
+pub struct Hash {
+    pub pairs: BTreeMap<HashKey, Box<dyn Object>>,
+    _allocation: Allocation,
+}
+
+impl Object for Hash {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "hash"
+    }
+
+    fn visit_environments(&self, visit: &mut dyn FnMut(&Environment)) {
+        for value in self.pairs.values() {
+            value.visit_environments(visit);
+        }
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Hash>() {
+            Some(other) => compare_sequences(
+                self.pairs.iter(),
+                other.pairs.iter(),
+                |(left_key, left_value), (right_key, right_value)| {
+                    left_key
+                        .cmp(right_key)
+                        .then_with(|| left_value.compare(right_value.as_ref()))
+                },
+            ),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
+    fn equals(&self, other: &dyn Object) -> bool {
+        match other.as_any().downcast_ref::<Hash>() {
+            Some(other) => {
+                self.pairs.len() == other.pairs.len()
+                    && self.pairs.iter().zip(&other.pairs).all(
+                        |((left_key, left_value), (right_key, right_value))| {
+                            left_key == right_key && left_value.equals(right_value.as_ref())
+                        },
+                    )
+            }
+            None => false,
+        }
+    }
+
+    fn inspect(&self) -> String {
+        let pairs_string = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key.to_object().inspect(), value.inspect()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("{{{pairs_string}}}")
+    }
+}
+
+impl Hash {
+    pub fn new(pairs: BTreeMap<HashKey, Box<dyn Object>>) -> Self {
+        let bytes = pairs
+            .iter()
+            .map(|(key, value)| {
+                let key_size = match key {
+                    HashKey::String(key) => mem::size_of::<HashKey>() + key.len(),
+                    HashKey::BigInteger(key) => mem::size_of::<HashKey>() + key.heap_size(),
+                    _ => mem::size_of::<HashKey>(),
+                };
+                key_size + boxed_size(value.as_ref())
+            })
+            .sum();
+        Self {
+            pairs,
+            _allocation: Allocation::new(bytes),
+        }
+    }
+}
+
+// ========== Hash End ==========
+
 // ========== String Start ==========
 
 #[derive(Clone)]
 pub struct StringObject {
     pub value: String,
+    _allocation: Allocation,
 }
 
 impl Object for StringObject {
@@ -107,22 +472,84 @@ impl Object for StringObject {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "string"
+    }
+
+    fn hash_key(&self) -> Option<HashKey> {
+        Some(HashKey::String(self.value.clone()))
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<StringObject>() {
+            Some(other) => self.value.cmp(&other.value),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
     fn inspect(&self) -> String {
-        format!("\"{}\"", self.value)
+        Lexer::escape_string(&self.value)
+    }
+
+    fn display(&self) -> String {
+        self.value.clone()
     }
 }
 
 impl StringObject {
     pub fn new(value: String) -> Self {
-        Self { value }
+        let bytes = value.len();
+        Self {
+            value,
+            _allocation: Allocation::new(bytes),
+        }
     }
 }
 
 // ========== String End ==========
 
-// ========== Null Start ==========
+// ========== Range Start ==========
 
+/// The integers from `start` up to but not including `end`, as returned by the `range` builtin.
+/// A for loop over a range goes through the integers without storing them all in an array.
 #[derive(Clone)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Object for Range {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "range"
+    }
+
+    fn inspect(&self) -> String {
+        format!("range({}, {})", self.start, self.end)
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Range>() {
+            Some(other) => (self.start, self.end).cmp(&(other.start, other.end)),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+}
+
+impl Range {
+    pub fn new(start: i64, end: i64) -> Self {
+        Self { start, end }
+    }
+}
+
+// ========== Range End ==========
+
+// ========== Null Start ==========
+
+#[derive(Clone, Default)]
 pub struct Null {}
 
 impl Object for Null {
@@ -130,6 +557,10 @@ impl Object for Null {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "null"
+    }
+
     fn inspect(&self) -> String {
         "null".to_string()
     }
@@ -147,9 +578,13 @@ impl Null {
 
 #[derive(Clone)]
 pub struct Function {
+    // The name the function was first bound to with `let`. This is only used for error reporting.
+    pub name: Option<String>,
     pub parameters: Vec<Identifier>,
-    pub body: BlockStatement,
+    // Shared with the function literal the function was created from
+    pub body: Rc<BlockStatement>,
     pub env: Environment,
+    _allocation: Allocation,
 }
 
 impl Object for Function {
@@ -157,6 +592,24 @@ impl Object for Function {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "function"
+    }
+
+    fn visit_environments(&self, visit: &mut dyn FnMut(&Environment)) {
+        visit(&self.env);
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<Function>() {
+            Some(other) => self
+                .inspect()
+                .cmp(&other.inspect())
+                .then_with(|| self.env.address().cmp(&other.env.address())),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
     fn inspect(&self) -> String {
         let parameter_string = self
             .parameters
@@ -169,11 +622,20 @@ impl Object for Function {
 }
 
 impl Function {
-    pub fn new(parameters: &[Identifier], body: BlockStatement, env: Environment) -> Self {
+    pub fn new(parameters: &[Identifier], body: Rc<BlockStatement>, env: Environment) -> Self {
+        env.capture();
+        let parameters_size = parameters
+            .iter()
+            .map(|parameter| {
+                mem::size_of::<Identifier>() + parameter.value.len() + parameter.token.literal.len()
+            })
+            .sum();
         Self {
+            name: None,
             parameters: parameters.to_vec(),
             body,
             env,
+            _allocation: Allocation::new(parameters_size),
         }
     }
 }
@@ -192,6 +654,14 @@ impl Object for ReturnValue {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "return value"
+    }
+
+    fn visit_environments(&self, visit: &mut dyn FnMut(&Environment)) {
+        self.value.visit_environments(visit);
+    }
+
     fn inspect(&self) -> String {
         self.value.inspect()
     }
@@ -205,11 +675,105 @@ impl ReturnValue {
 
 // ========== ReturnValue End ==========
 
+// ========== TailCall Start ==========
+
+// A call to a Donkey function in tail position, which is what the evaluator returns instead of
+// making the call. The function whose body the call is in returns it, and `apply_function` then
+// makes the call in its place, so tail recursion doesn't grow the Rust stack.
+#[derive(Clone)]
+pub struct TailCall {
+    pub function: Function,
+    pub arguments: Vec<Box<dyn Object>>,
+    pub call_site: Span,
+}
+
+impl Object for TailCall {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "tail call"
+    }
+
+    fn visit_environments(&self, visit: &mut dyn FnMut(&Environment)) {
+        self.function.visit_environments(visit);
+        for argument in &self.arguments {
+            argument.visit_environments(visit);
+        }
+    }
+
+    fn inspect(&self) -> String {
+        let arguments: Vec<String> = self.arguments.iter().map(|arg| arg.inspect()).collect();
+        format!("{}({})", self.function.inspect(), arguments.join(", "))
+    }
+}
+
+impl TailCall {
+    pub fn new(function: Function, arguments: Vec<Box<dyn Object>>, call_site: Span) -> Self {
+        Self {
+            function,
+            arguments,
+            call_site,
+        }
+    }
+}
+
+// ========== TailCall End ==========
+
+// ========== Loop control Start ==========
+
+// Break and Continue are what break and continue statements evaluate to. Like ReturnValue, they
+// stop the evaluation of the blocks they are in until they reach the innermost loop, which
+// handles them.
+
+#[derive(Clone)]
+pub struct Break {}
+
+impl Object for Break {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "break"
+    }
+
+    fn inspect(&self) -> String {
+        "break".to_string()
+    }
+}
+
+#[derive(Clone)]
+pub struct Continue {}
+
+impl Object for Continue {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        "continue"
+    }
+
+    fn inspect(&self) -> String {
+        "continue".to_string()
+    }
+}
+
+// ========== Loop control End ==========
+
 // ========== BuiltinFn Start ==========
 
+pub type BuiltinFunction = Rc<dyn Fn(Arguments) -> Result<Box<dyn Object>, EvaluatorError>>;
+
+/// A function implemented in Rust that can be called from Donkey code. The evaluator checks that
+/// calls pass a number of arguments allowed by `arity` before calling `builtin_fn`.
 #[derive(Clone)]
 pub struct BuiltinFn {
-    pub builtin_fn: Rc<dyn Fn(Vec<Box<dyn Object>>) -> Result<Box<dyn Object>, EvaluatorError>>,
+    pub name: String,
+    pub arity: Arity,
+    pub builtin_fn: BuiltinFunction,
 }
 
 impl Object for BuiltinFn {
@@ -217,36 +781,157 @@ impl Object for BuiltinFn {
         self
     }
 
+    fn type_name(&self) -> &'static str {
+        "builtin function"
+    }
+
+    fn compare(&self, other: &dyn Object) -> Ordering {
+        match other.as_any().downcast_ref::<BuiltinFn>() {
+            // Builtins registered under the same name are only equal if they are the same function
+            Some(other) => self.name.cmp(&other.name).then_with(|| {
+                Rc::as_ptr(&self.builtin_fn)
+                    .cast::<()>()
+                    .cmp(&Rc::as_ptr(&other.builtin_fn).cast::<()>())
+            }),
+            None => compare_kinds(self.as_any(), other.as_any()),
+        }
+    }
+
     fn inspect(&self) -> String {
         "builtin_function".to_string()
     }
 }
 
 impl BuiltinFn {
-    pub fn new(
-        builtin_fn: Rc<dyn Fn(Vec<Box<dyn Object>>) -> Result<Box<dyn Object>, EvaluatorError>>,
-    ) -> Self {
-        Self { builtin_fn }
+    pub fn new<F>(name: &str, arity: Arity, builtin_fn: F) -> Self
+    where
+        F: Fn(Arguments) -> Result<Box<dyn Object>, EvaluatorError> + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            arity,
+            builtin_fn: Rc::new(builtin_fn),
+        }
     }
 }
 
 // ========== BuiltinFn End ==========
 
-#[derive(Clone)]
-pub struct SelfRef {}
+// ========== Ordering Helpers Start ==========
+
+// The position of an object's kind in the ordering documented on `Object::compare`
+fn kind_rank(object: &dyn Any) -> u8 {
+    if object.is::<Null>() {
+        0
+    } else if object.is::<Boolean>() {
+        1
+    } else if object.is::<Integer>() || object.is::<BigInteger>() || object.is::<Float>() {
+        2
+    } else if object.is::<StringObject>() {
+        3
+    } else if object.is::<Array>() {
+        4
+    } else if object.is::<Hash>() {
+        5
+    } else if object.is::<Range>() {
+        6
+    } else if object.is::<Function>() {
+        7
+    } else if object.is::<BuiltinFn>() {
+        8
+    } else {
+        9
+    }
+}
 
-impl Object for SelfRef {
-    fn as_any(&self) -> &dyn Any {
-        self
+fn compare_kinds(left: &dyn Any, right: &dyn Any) -> Ordering {
+    kind_rank(left).cmp(&kind_rank(right))
+}
+
+enum Number {
+    Integer(BigInt),
+    Float(f64),
+}
+
+impl Number {
+    fn from_object(object: &dyn Any) -> Option<Number> {
+        if let Some(integer) = object.downcast_ref::<Integer>() {
+            Some(Number::Integer(BigInt::from(integer.value)))
+        } else if let Some(integer) = object.downcast_ref::<BigInteger>() {
+            Some(Number::Integer(integer.value.clone()))
+        } else {
+            object
+                .downcast_ref::<Float>()
+                .map(|float| Number::Float(float.value))
+        }
     }
+}
 
-    fn inspect(&self) -> String {
-        "self_ref_obj".to_string()
+// Compares two numbers by value, or returns `None` if either of the objects isn't a number
+fn compare_numbers(left: &dyn Any, right: &dyn Any) -> Option<Ordering> {
+    let ordering = match (Number::from_object(left)?, Number::from_object(right)?) {
+        (Number::Integer(left), Number::Integer(right)) => left.cmp(&right),
+        (Number::Integer(left), Number::Float(right)) => compare_integer_to_float(&left, right),
+        (Number::Float(left), Number::Integer(right)) => {
+            compare_integer_to_float(&right, left).reverse()
+        }
+        (Number::Float(left), Number::Float(right)) => match (left.is_nan(), right.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => left.partial_cmp(&right).unwrap_or(Ordering::Equal),
+        },
+    };
+    Some(ordering)
+}
+
+// Compares an integer with a float exactly, rather than converting the integer to a float and
+// losing precision
+fn compare_integer_to_float(integer: &BigInt, float: f64) -> Ordering {
+    if float.is_nan() {
+        return Ordering::Less;
+    }
+    let Some(integer) = integer.to_i64() else {
+        // Integers that don't fit in an `i64` are only compared approximately
+        return integer
+            .to_f64()
+            .partial_cmp(&float)
+            .unwrap_or(Ordering::Equal);
+    };
+    // 2^63 is exactly representable, so these bounds are exact
+    if float >= 9_223_372_036_854_775_808.0 {
+        return Ordering::Less;
     }
+    if float < -9_223_372_036_854_775_808.0 {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    integer
+        .cmp(&(whole as i64))
+        .then_with(|| whole.partial_cmp(&float).unwrap_or(Ordering::Equal))
 }
 
-impl SelfRef {
-    pub fn new() -> Self {
-        Self {}
+// Compares two sequences lexicographically, so a sequence comes before any longer sequence it is
+// a prefix of
+fn compare_sequences<T>(
+    left: impl Iterator<Item = T>,
+    mut right: impl Iterator<Item = T>,
+    compare: impl Fn(T, T) -> Ordering,
+) -> Ordering {
+    for left_item in left {
+        let Some(right_item) = right.next() else {
+            return Ordering::Greater;
+        };
+        let ordering = compare(left_item, right_item);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    if right.next().is_some() {
+        Ordering::Less
+    } else {
+        Ordering::Equal
     }
 }
+
+// ========== Ordering Helpers End ==========
@@ -0,0 +1,176 @@
+#[cfg(test)]
+use std::cmp::Ordering;
+
+#[cfg(test)]
+use crate::object::{
+    Array, BigInt, ConversionError, Float, FromObject, Integer, IntoObject, Null, Object,
+};
+
+#[test]
+fn rust_values_into_objects() {
+    let tests: Vec<(Box<dyn Object>, &str)> = vec![
+        (5.into_object(), "5"),
+        (true.into_object(), "true"),
+        (2.5.into_object(), "2.5"),
+        ("hi".into_object(), "\"hi\""),
+        ("hi".to_string().into_object(), "\"hi\""),
+        (vec![1, 2, 3].into_object(), "[1, 2, 3]"),
+        (vec![vec![true], vec![]].into_object(), "[[true], []]"),
+        (Some(3).into_object(), "3"),
+        (None::<i64>.into_object(), "null"),
+        (().into_object(), "null"),
+    ];
+    for (object, expected) in tests {
+        assert_eq!(object.inspect(), expected);
+    }
+}
+
+#[test]
+fn objects_into_rust_values() {
+    assert_eq!(i64::from_object(&Integer::new(7)), Ok(7));
+    assert_eq!(bool::from_object(true.into_object().as_ref()), Ok(true));
+    assert_eq!(f64::from_object(0.5.into_object().as_ref()), Ok(0.5));
+    assert_eq!(f64::from_object(&Integer::new(2)), Ok(2.0));
+    assert_eq!(
+        String::from_object("abc".into_object().as_ref()),
+        Ok("abc".to_string())
+    );
+    assert_eq!(
+        Vec::<Vec<i64>>::from_object(vec![vec![1], vec![2, 3]].into_object().as_ref()),
+        Ok(vec![vec![1], vec![2, 3]])
+    );
+    assert_eq!(Option::<i64>::from_object(&Null::new()), Ok(None));
+    assert_eq!(Option::<i64>::from_object(&Integer::new(1)), Ok(Some(1)));
+    assert_eq!(<()>::from_object(&Null::new()), Ok(()));
+
+    let object: Box<dyn Object> = Box::new(Array::new(vec![1.into_object()]));
+    let converted = Box::<dyn Object>::from_object(object.as_ref()).unwrap();
+    assert_eq!(converted.inspect(), "[1]");
+}
+
+#[test]
+fn conversion_errors() {
+    let tests: Vec<(Result<(), ConversionError>, &str)> = vec![
+        (
+            i64::from_object(true.into_object().as_ref()).map(|_| ()),
+            "Expected integer, found boolean",
+        ),
+        (
+            String::from_object(&Null::new()).map(|_| ()),
+            "Expected string, found null",
+        ),
+        (
+            Vec::<i64>::from_object(&Integer::new(1)).map(|_| ()),
+            "Expected array, found integer",
+        ),
+        (
+            Vec::<Vec<i64>>::from_object(
+                vec![
+                    vec![1.into_object()],
+                    vec![2.into_object(), "x".into_object()],
+                ]
+                .into_object()
+                .as_ref(),
+            )
+            .map(|_| ()),
+            "Expected integer, found string at index [1][1]",
+        ),
+        (
+            <()>::from_object(&Integer::new(0)),
+            "Expected null, found integer",
+        ),
+    ];
+    for (result, expected) in tests {
+        assert_eq!(result.unwrap_err().to_string(), expected);
+    }
+}
+
+#[test]
+fn big_integer_arithmetic() {
+    let big = |value: i64| BigInt::from(value);
+    let max = big(i64::MAX);
+    let min = big(i64::MIN);
+
+    assert_eq!((&max + &big(1)).to_string(), "9223372036854775808");
+    assert_eq!((&min - &big(1)).to_string(), "-9223372036854775809");
+    assert_eq!(
+        (&min * &min).to_string(),
+        "85070591730234615865843651857942052864"
+    );
+    assert_eq!((&max * &big(-1)).to_i64(), Some(-i64::MAX));
+    assert_eq!((&max + &big(1)).to_i64(), None);
+    assert_eq!((-&min).to_i64(), None);
+    assert_eq!((&(&max * &max) - &(&max * &max)).to_string(), "0");
+    assert_eq!(big(-5).to_string(), "-5");
+    assert_eq!(big(1_000_000_000).to_string(), "1000000000");
+
+    let product = &(&max * &max) + &big(7);
+    assert_eq!(product.checked_div(&max), Some(max.clone()));
+    assert_eq!(product.checked_div(&-&max), Some(-&max));
+    assert_eq!(big(-7).checked_div(&big(2)), Some(big(-3)));
+    assert_eq!(big(7).checked_div(&big(0)), None);
+
+    assert!(min < big(-1));
+    assert!(&max + &big(1) > max);
+    assert!(&min - &big(1) < min);
+    assert_eq!(
+        (&max * &big(2)).into_object().inspect(),
+        "18446744073709551614"
+    );
+    assert_eq!(BigInt::from_object(&Integer::new(3)), Ok(big(3)));
+}
+
+#[test]
+fn object_ordering() {
+    // Each object is less than the ones after it
+    let ordered: Vec<Box<dyn Object>> = vec![
+        Box::new(Null::new()),
+        false.into_object(),
+        true.into_object(),
+        Box::new(Float::new(f64::NEG_INFINITY)),
+        (&BigInt::from(i64::MIN) * &BigInt::from(2)).into_object(),
+        (-1).into_object(),
+        Box::new(Float::new(-0.5)),
+        0.into_object(),
+        Box::new(Float::new(0.5)),
+        i64::MAX.into_object(),
+        Box::new(Float::new(9_223_372_036_854_775_808.0)),
+        Box::new(Float::new(f64::NAN)),
+        "".into_object(),
+        "a".into_object(),
+        "ab".into_object(),
+        "b".into_object(),
+        Vec::<i64>::new().into_object(),
+        vec![1].into_object(),
+        vec![1, 2].into_object(),
+        vec![2].into_object(),
+    ];
+    for (i, left) in ordered.iter().enumerate() {
+        for (j, right) in ordered.iter().enumerate() {
+            assert_eq!(
+                left.compare(right.as_ref()),
+                i.cmp(&j),
+                "{} compared to {}",
+                left.inspect(),
+                right.inspect()
+            );
+        }
+    }
+
+    // i64::MAX can't be represented exactly as a float, so it must not equal the float nearest
+    // to it
+    let nearest = Float::new(i64::MAX as f64);
+    assert_eq!(nearest.compare(&Integer::new(i64::MAX)), Ordering::Greater);
+    assert!(Integer::new(1).equals(&Float::new(1.0)));
+    assert!(Float::new(-0.0).equals(&Integer::new(0)));
+
+    // NaN has a place in the ordering but isn't equal to anything, including itself
+    let nan = Float::new(f64::NAN);
+    assert_eq!(nan.compare(&Float::new(f64::NAN)), Ordering::Equal);
+    assert!(!nan.equals(&Float::new(f64::NAN)));
+    assert!(
+        !vec![f64::NAN]
+            .into_object()
+            .equals(vec![f64::NAN].into_object().as_ref())
+    );
+}
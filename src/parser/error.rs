@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::lexer::LexerErrorKind;
+use crate::token::{Span, TokenType};
+
+/// The different kinds of errors the parser can report. Each kind has a stable error code (see
+/// `ParserErrorKind::code`) that tooling can match on instead of the error message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParserErrorKind {
+    /// A specific token was required but a different one was found.
+    UnexpectedToken {
+        expected: TokenType,
+        found: TokenType,
+    },
+    /// The token cannot start an expression.
+    NoPrefixParseFunction { found: TokenType },
+    /// An integer literal that doesn't fit in an i64.
+    InvalidIntegerLiteral { literal: String },
+    /// A float literal that is too large for an f64.
+    InvalidFloatLiteral { literal: String },
+    /// A boolean literal other than `true` or `false`.
+    InvalidBooleanLiteral { literal: String },
+    /// A `let rec` statement bound something other than a function literal.
+    RecursiveBindingNotFunction { name: String },
+    /// A `break` or `continue` statement that isn't inside a loop. `keyword` is the statement's
+    /// keyword.
+    LoopControlOutsideLoop { keyword: String },
+    /// The left side of an assignment isn't a variable or an index expression.
+    InvalidAssignmentTarget { operator: String },
+    /// An error found by the lexer while reading the tokens.
+    Lexer(LexerErrorKind),
+}
+
+impl ParserErrorKind {
+    /// Returns the stable error code for this kind of error. Parser error codes start with `P`.
+    /// Codes of errors that were removed are not reused.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserErrorKind::UnexpectedToken { .. } => "P0001",
+            ParserErrorKind::NoPrefixParseFunction { .. } => "P0002",
+            ParserErrorKind::InvalidIntegerLiteral { .. } => "P0003",
+            ParserErrorKind::InvalidBooleanLiteral { .. } => "P0004",
+            ParserErrorKind::RecursiveBindingNotFunction { .. } => "P0006",
+            ParserErrorKind::InvalidFloatLiteral { .. } => "P0007",
+            ParserErrorKind::LoopControlOutsideLoop { .. } => "P0008",
+            ParserErrorKind::InvalidAssignmentTarget { .. } => "P0009",
+            ParserErrorKind::Lexer(kind) => kind.code(),
+        }
+    }
+}
+
+impl fmt::Display for ParserErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "Expected {expected:?}, found {found:?} instead")
+            }
+            ParserErrorKind::NoPrefixParseFunction { found } => {
+                write!(f, "Expected an expression, found {found:?} instead")
+            }
+            ParserErrorKind::InvalidIntegerLiteral { literal } => {
+                write!(f, "Could not parse {literal} as an integer")
+            }
+            ParserErrorKind::InvalidFloatLiteral { literal } => {
+                write!(f, "Could not parse {literal} as a float")
+            }
+            ParserErrorKind::InvalidBooleanLiteral { literal } => {
+                write!(f, "Could not parse {literal} as a bool")
+            }
+            ParserErrorKind::RecursiveBindingNotFunction { name } => {
+                write!(
+                    f,
+                    "Expected a function literal in recursive binding of {name}"
+                )
+            }
+            ParserErrorKind::LoopControlOutsideLoop { keyword } => {
+                write!(f, "Found `{keyword}` outside of a loop")
+            }
+            ParserErrorKind::InvalidAssignmentTarget { operator } => {
+                write!(f, "Invalid left side of {operator}")
+            }
+            ParserErrorKind::Lexer(kind) => write!(f, "{kind}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParserError {
+    pub kind: ParserErrorKind,
+    pub span: Span,
+}
+
+impl ParserError {
+    pub fn new(kind: ParserErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Returns a suggestion for fixing the error, if there is a useful one.
+    pub fn help(&self) -> Option<&'static str> {
+        match &self.kind {
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::Semicolon,
+                ..
+            } => Some("let, return, break and continue statements must end with a `;`"),
+            ParserErrorKind::RecursiveBindingNotFunction { .. } => {
+                Some("only functions can be bound with `let rec`, use `let` for other values")
+            }
+            ParserErrorKind::LoopControlOutsideLoop { .. } => Some(
+                "break and continue can only be used in the body of a while or for loop, and not in functions defined there",
+            ),
+            ParserErrorKind::InvalidAssignmentTarget { .. } => {
+                Some("only variables and index expressions like `a[i]` can be assigned to")
+            }
+            ParserErrorKind::Lexer(kind) => kind.help(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.start.line, self.span.start.column, self.kind
+        )
+    }
+}
+
+impl Error for ParserError {}
@@ -1,11 +1,15 @@
+pub mod error;
+
 mod tests;
 
 use std::collections::HashMap;
 
 use crate::ast::{
-    ArrayExpression, BlockStatement, BooleanLiteral, CallExpression, Expression,
-    ExpressionStatement, FunctionLiteral, IfExpression, IndexExpression, InfixExpression,
-    IntegerLiteral, NullLiteral, PrefixExpression, ReturnStatement, StringExpression,
+    ArrayExpression, AssignExpression, BlockStatement, BooleanLiteral, BreakStatement,
+    CallExpression, ContinueStatement, Expression, ExpressionStatement, FloatLiteral, ForStatement,
+    FunctionLiteral, HashExpression, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, InterpolatedStringExpression, NullLiteral, PrefixExpression, ReturnStatement,
+    SliceExpression, StringExpression, WhileStatement,
 };
 
 type PrefixParseFn = fn(&mut Parser) -> Option<Box<dyn Expression>>;
@@ -17,11 +21,16 @@ use crate::{
     token::{Token, TokenType},
 };
 
+pub use error::{ParserError, ParserErrorKind};
+
 pub struct Parser {
     lexer: Lexer,
     cur_token: Token,
     peek_token: Token,
-    errors: Vec<String>,
+    errors: Vec<ParserError>,
+    // The number of loops around the current token, not counting loops outside of the innermost
+    // function literal. Used to check that break and continue statements are inside a loop.
+    loop_depth: usize,
     prefix_parse_functions: HashMap<TokenType, PrefixParseFn>,
     infix_parse_functions: HashMap<TokenType, InfixParseFn>,
 }
@@ -35,6 +44,7 @@ impl Parser {
             cur_token: Token::new(TokenType::Int, "6"),
             peek_token: Token::new(TokenType::Int, "9"),
             errors: Vec::new(),
+            loop_depth: 0,
             prefix_parse_functions: HashMap::new(),
             infix_parse_functions: HashMap::new(),
         };
@@ -46,6 +56,7 @@ impl Parser {
         // Register the prefix functions
         parser.register_prefix_function(TokenType::Ident, |parser| parser.parse_identifier());
         parser.register_prefix_function(TokenType::Int, |parser| parser.parse_integer_literal());
+        parser.register_prefix_function(TokenType::Float, |parser| parser.parse_float_literal());
         parser.register_prefix_function(TokenType::Null, |parser| parser.parse_null());
         parser.register_prefix_function(TokenType::True, |parser| parser.parse_boolean_literal());
         parser.register_prefix_function(TokenType::False, |parser| parser.parse_boolean_literal());
@@ -57,9 +68,13 @@ impl Parser {
         });
         parser
             .register_prefix_function(TokenType::LSquare, |parser| parser.parse_array_expression());
-        parser.register_prefix_function(TokenType::DoubleQuotation, |parser| {
+        parser.register_prefix_function(TokenType::LBrace, |parser| parser.parse_hash_expression());
+        parser.register_prefix_function(TokenType::StringValue, |parser| {
             parser.parse_string_expression()
         });
+        parser.register_prefix_function(TokenType::StringStart, |parser| {
+            parser.parse_interpolated_string_expression()
+        });
         parser.register_prefix_function(TokenType::If, |parser| parser.parse_if_expression());
         parser.register_prefix_function(TokenType::Function, |parser| {
             parser.parse_function_literal()
@@ -96,6 +111,27 @@ impl Parser {
         parser.register_infix_function(TokenType::Slash, |parser, left| {
             parser.parse_infix_expression(left)
         });
+        parser.register_infix_function(TokenType::Percent, |parser, left| {
+            parser.parse_infix_expression(left)
+        });
+        parser.register_infix_function(TokenType::LogicalAnd, |parser, left| {
+            parser.parse_infix_expression(left)
+        });
+        parser.register_infix_function(TokenType::LogicalOr, |parser, left| {
+            parser.parse_infix_expression(left)
+        });
+        for token_type in [
+            TokenType::Assign,
+            TokenType::PlusAssign,
+            TokenType::MinusAssign,
+            TokenType::StarAssign,
+            TokenType::SlashAssign,
+            TokenType::PercentAssign,
+        ] {
+            parser.register_infix_function(token_type, |parser, left| {
+                parser.parse_assign_expression(left)
+            });
+        }
         parser.register_infix_function(TokenType::LParen, |parser, left| {
             parser.parse_call_expression(left)
         });
@@ -123,8 +159,11 @@ impl Parser {
     // ending semicolon.
     fn parse_statement(&mut self) -> Option<Box<dyn Statement>> {
         match self.cur_token.token_type {
-            TokenType::Let => self.parse_let_statement(),
+            TokenType::Let | TokenType::Var => self.parse_let_statement(),
             TokenType::Return => self.parse_return_statement(),
+            TokenType::While => self.parse_while_statement(),
+            TokenType::For => self.parse_for_statement(),
+            TokenType::Break | TokenType::Continue => self.parse_loop_control_statement(),
             // Default case is assume we are parsing an expression statement
             _ => self.parse_expression_statement(),
         }
@@ -140,26 +179,55 @@ impl Parser {
         };
         self.next_token();
         let mut statements = Vec::new();
-        while self.peek_token.token_type != TokenType::Eof {
+        // Checking for the RBrace before parsing the first statement allows empty blocks
+        while self.cur_token.token_type != TokenType::RBrace
+            && self.peek_token.token_type != TokenType::Eof
+        {
             let statement = self.parse_statement()?;
             statements.push(statement);
-            if self.cur_token.token_type == TokenType::RBrace {
-                break;
-            }
         }
-        Some(BlockStatement::new(token, statements))
+        let mut block_statement = BlockStatement::new(token, statements);
+        block_statement.span.end = self.cur_token.span.end;
+        Some(block_statement)
     }
 
-    // When this function is called, cur_token should be pointing to the Let
+    // When this function is called, cur_token should be pointing to the Let or Var
     fn parse_let_statement(&mut self) -> Option<Box<dyn Statement>> {
         // This check is technically not needed since if we enter this function,
-        // the current token should have TokenType::Let.
-        let token = if self.cur_token.token_type == TokenType::Let {
+        // the current token should have TokenType::Let or TokenType::Var.
+        let token = if matches!(self.cur_token.token_type, TokenType::Let | TokenType::Var) {
             self.cur_token.clone()
         } else {
             return None;
         };
-        let rec = self.expect_peek(TokenType::Rec);
+        // Functions bound with let rec refer to each other through their bindings, so those
+        // bindings can't be mutable
+        let mutable = token.token_type == TokenType::Var;
+        let rec = !mutable && self.expect_peek(TokenType::Rec);
+        let (name, value) = self.parse_let_binding(rec)?;
+        let mut let_statement = LetStatement::new(token, name, value, rec);
+        let_statement.mutable = mutable;
+        // Only let rec statements can define several bindings, since the point of defining them
+        // together is that they can refer to each other
+        while rec && self.expect_peek(TokenType::And) {
+            let (name, value) = self.parse_let_binding(rec)?;
+            let_statement.span.end = value.span().end;
+            let_statement.and_bindings.push((name, value));
+        }
+        // Advance token to the semicolon
+        if !self.expect_peek(TokenType::Semicolon) {
+            self.expect_error(TokenType::Semicolon);
+            self.next_token();
+        } else {
+            let_statement.span.end = self.cur_token.span.end;
+            self.next_token();
+        }
+        Some(Box::new(let_statement))
+    }
+
+    // Parses `name = value` in a let statement. When this function is called, self.cur_token
+    // should be pointing to the token before the name.
+    fn parse_let_binding(&mut self, rec: bool) -> Option<(Identifier, Box<dyn Expression>)> {
         // If the next token is TokenType::Ident, then we advance the token pointers.
         // Then `cur_token` points to the Identifier token.
         let name = if self.expect_peek(TokenType::Ident) {
@@ -177,14 +245,18 @@ impl Parser {
         // Advance token to start of expression
         self.next_token();
         let value = self.parse_expression(Precedence::Lowest as i32)?;
-        // Advance token to the semicolon
-        self.next_token();
-        if self.cur_token.token_type != TokenType::Semicolon {
-            self.expect_error(TokenType::Semicolon);
-        } else {
-            self.next_token();
+        // Recursive bindings can only refer to each other once they are all defined, which only
+        // works if evaluating them doesn't need the other bindings yet
+        if rec && !value.as_any().is::<FunctionLiteral>() {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::RecursiveBindingNotFunction {
+                    name: name.value.clone(),
+                },
+                value.span(),
+            ));
+            return None;
         }
-        Some(Box::new(LetStatement::new(token, name, value, rec)))
+        Some((name, value))
     }
 
     // When this function is called, self.cur_token should be pointing to a token with
@@ -200,19 +272,106 @@ impl Parser {
         // Advance token to start of expression
         self.next_token();
         let return_value = self.parse_expression(Precedence::Lowest as i32)?;
-        // Advance token token to the semicolon
+        let mut return_statement = ReturnStatement::new(token, return_value);
+        // Advance token to the semicolon
+        if !self.expect_peek(TokenType::Semicolon) {
+            self.expect_error(TokenType::Semicolon);
+            self.next_token();
+        } else {
+            return_statement.span.end = self.cur_token.span.end;
+            self.next_token();
+        }
+        Some(Box::new(return_statement))
+    }
+
+    // When this function is called, self.cur_token should be pointing to the While
+    fn parse_while_statement(&mut self) -> Option<Box<dyn Statement>> {
+        let token = self.cur_token.clone();
+        // Advance token to start of the condition. Brackets around the condition are optional,
+        // since they are parsed as part of the expression.
         self.next_token();
-        if self.cur_token.token_type != TokenType::Semicolon {
+        let condition = self.parse_expression(Precedence::Lowest as i32)?;
+        let body = self.parse_loop_body()?;
+        Some(Box::new(WhileStatement::new(token, condition, body)))
+    }
+
+    // When this function is called, self.cur_token should be pointing to the For
+    fn parse_for_statement(&mut self) -> Option<Box<dyn Statement>> {
+        let token = self.cur_token.clone();
+        let variable = if self.expect_peek(TokenType::Ident) {
+            Identifier::new(self.cur_token.clone(), &self.cur_token.literal)
+        } else {
+            self.expect_error(TokenType::Ident);
+            return None;
+        };
+        if !self.expect_peek(TokenType::In) {
+            self.expect_error(TokenType::In);
+            return None;
+        }
+        // Advance token to start of the collection
+        self.next_token();
+        let collection = self.parse_expression(Precedence::Lowest as i32)?;
+        let body = self.parse_loop_body()?;
+        Some(Box::new(ForStatement::new(
+            token, variable, collection, body,
+        )))
+    }
+
+    // Parses the body of a loop. When this function is called, self.cur_token should be pointing
+    // to the last token before the LBrace. Like other statements, it leaves the parser at the
+    // first token of the next statement, skipping an optional semicolon after the body.
+    fn parse_loop_body(&mut self) -> Option<BlockStatement> {
+        if !self.expect_peek(TokenType::LBrace) {
+            self.expect_error(TokenType::LBrace);
+            return None;
+        }
+        self.loop_depth += 1;
+        let body = self.parse_block_statement();
+        self.loop_depth -= 1;
+        let body = body?;
+        self.next_token();
+        if self.cur_token.token_type == TokenType::Semicolon {
+            self.next_token();
+        }
+        Some(body)
+    }
+
+    // When this function is called, self.cur_token should be pointing to the Break or Continue
+    fn parse_loop_control_statement(&mut self) -> Option<Box<dyn Statement>> {
+        let token = self.cur_token.clone();
+        if self.loop_depth == 0 {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::LoopControlOutsideLoop {
+                    keyword: token.literal.clone(),
+                },
+                token.span,
+            ));
+        }
+        let mut span = token.span;
+        // Advance token to the semicolon
+        if !self.expect_peek(TokenType::Semicolon) {
             self.expect_error(TokenType::Semicolon);
+            self.next_token();
         } else {
+            span.end = self.cur_token.span.end;
             self.next_token();
         }
-        Some(Box::new(ReturnStatement::new(token, return_value)))
+        let statement: Box<dyn Statement> = if token.token_type == TokenType::Break {
+            let mut break_statement = BreakStatement::new(token);
+            break_statement.span = span;
+            Box::new(break_statement)
+        } else {
+            let mut continue_statement = ContinueStatement::new(token);
+            continue_statement.span = span;
+            Box::new(continue_statement)
+        };
+        Some(statement)
     }
 
     fn parse_expression_statement(&mut self) -> Option<Box<dyn Statement>> {
         let token = self.cur_token.clone();
         let expression = self.parse_expression(Precedence::Lowest as i32)?;
+        let mut expression_statement = ExpressionStatement::new(token, expression);
         // let requires_semi = self.expr_requires_semi_to_be_stmt(&*expression);
         // Advance token to potentially a semicolon
         self.next_token();
@@ -233,9 +392,10 @@ impl Parser {
         //     self.next_token();
         // }
         if self.cur_token.token_type == TokenType::Semicolon {
+            expression_statement.span.end = self.cur_token.span.end;
             self.next_token();
         }
-        Some(Box::new(ExpressionStatement::new(token, expression)))
+        Some(Box::new(expression_statement))
     }
 
     /// Parses an expression and returns an AST node representing that expression.
@@ -289,8 +449,29 @@ impl Parser {
         match token.literal.parse::<i64>() {
             Ok(value) => Some(Box::new(IntegerLiteral::new(token, value))),
             Err(_) => {
-                self.errors
-                    .push(format!("Could not parse {} as integer", token.literal));
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::InvalidIntegerLiteral {
+                        literal: token.literal,
+                    },
+                    token.span,
+                ));
+                None
+            }
+        }
+    }
+
+    fn parse_float_literal(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone();
+        match token.literal.parse::<f64>() {
+            // Literals too large for an f64 parse to infinity, which is never what was meant
+            Ok(value) if value.is_finite() => Some(Box::new(FloatLiteral::new(token, value))),
+            _ => {
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::InvalidFloatLiteral {
+                        literal: token.literal,
+                    },
+                    token.span,
+                ));
                 None
             }
         }
@@ -301,8 +482,12 @@ impl Parser {
         match token.literal.parse::<bool>() {
             Ok(value) => Some(Box::new(BooleanLiteral::new(token, value))),
             Err(_) => {
-                self.errors
-                    .push(format!("Could not parse {} as a bool", token.literal));
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::InvalidBooleanLiteral {
+                        literal: token.literal,
+                    },
+                    token.span,
+                ));
                 None
             }
         }
@@ -332,19 +517,51 @@ impl Parser {
         )))
     }
 
-    fn parse_call_expression(&mut self, left: Box<dyn Expression>) -> Option<Box<dyn Expression>> {
-        if !(left.as_any().is::<Identifier>() || left.as_any().is::<FunctionLiteral>()) {
-            self.errors
-                .push("Expected function literal or identifier in call position".to_string());
+    // When this function is called, self.cur_token should be pointing to the assignment operator
+    fn parse_assign_expression(
+        &mut self,
+        target: Box<dyn Expression>,
+    ) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone();
+        if !Self::is_assignment_target(target.as_ref()) {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::InvalidAssignmentTarget {
+                    operator: token.literal.clone(),
+                },
+                target.span(),
+            ));
             return None;
         }
+        let operator = token.literal.clone();
+        self.next_token();
+        // Assignments are right associative, so `a = b = c` assigns `c` to `b` and then to `a`
+        let value = self.parse_expression(Precedence::Lowest as i32)?;
+        Some(Box::new(AssignExpression::new(
+            token, target, &operator, value,
+        )))
+    }
+
+    fn is_assignment_target(target: &dyn Expression) -> bool {
+        if target.as_any().is::<Identifier>() {
+            true
+        } else if let Some(index_expression) = target.as_any().downcast_ref::<IndexExpression>() {
+            Self::is_assignment_target(index_expression.collection.as_ref())
+        } else {
+            false
+        }
+    }
+
+    fn parse_call_expression(&mut self, left: Box<dyn Expression>) -> Option<Box<dyn Expression>> {
         let token = if self.cur_token.token_type == TokenType::LParen {
             self.cur_token.clone()
         } else {
             return None;
         };
         let arguments = self.parse_argument_list()?;
-        Some(Box::new(CallExpression::new(token, left, arguments)))
+        let mut call_expression = CallExpression::new(token, left, arguments);
+        // cur_token points to the RParen here
+        call_expression.span.end = self.cur_token.span.end;
+        Some(Box::new(call_expression))
     }
 
     fn parse_index_expression(&mut self, left: Box<dyn Expression>) -> Option<Box<dyn Expression>> {
@@ -353,14 +570,47 @@ impl Parser {
         } else {
             return None;
         };
+        // A colon right after the LSquare is a slice without a start
+        if self.expect_peek(TokenType::Colon) {
+            return self.parse_slice_expression(token, left, None);
+        }
         // Advance cur_token so it points to the first token of the index value
         self.next_token();
         let index = self.parse_expression(Precedence::Lowest as i32)?;
+        if self.expect_peek(TokenType::Colon) {
+            return self.parse_slice_expression(token, left, Some(index));
+        }
         if !self.expect_peek(TokenType::RSquare) {
             self.expect_error(TokenType::RSquare);
             return None;
         }
-        Some(Box::new(IndexExpression::new(token, left, index)))
+        let mut index_expression = IndexExpression::new(token, left, index);
+        index_expression.span.end = self.cur_token.span.end;
+        Some(Box::new(index_expression))
+    }
+
+    // When this function is called, cur_token should point to the Colon of the slice.
+    // When it returns, cur_token should point to the RSquare.
+    fn parse_slice_expression(
+        &mut self,
+        token: Token,
+        left: Box<dyn Expression>,
+        start: Option<Box<dyn Expression>>,
+    ) -> Option<Box<dyn Expression>> {
+        let end = if self.expect_peek(TokenType::RSquare) {
+            None
+        } else {
+            self.next_token();
+            let end = self.parse_expression(Precedence::Lowest as i32)?;
+            if !self.expect_peek(TokenType::RSquare) {
+                self.expect_error(TokenType::RSquare);
+                return None;
+            }
+            Some(end)
+        };
+        let mut slice_expression = SliceExpression::new(token, left, start, end);
+        slice_expression.span.end = self.cur_token.span.end;
+        Some(Box::new(slice_expression))
     }
 
     fn parse_grouped_expression(&mut self) -> Option<Box<dyn Expression>> {
@@ -448,8 +698,12 @@ impl Parser {
             self.expect_error(TokenType::LBrace);
             return None;
         }
-        // cur_token now points to the LBrace
-        let body = self.parse_block_statement()?;
+        // cur_token now points to the LBrace. Loops around the function literal don't count
+        // for break and continue statements in its body, since they can't stop those loops.
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = self.parse_block_statement();
+        self.loop_depth = loop_depth;
+        let body = body?;
 
         Some(Box::new(FunctionLiteral::new(token, parameters, body)))
     }
@@ -534,40 +788,98 @@ impl Parser {
             }
         }
         // cur_token points to the RSqaure here
-        Some(Box::new(ArrayExpression::new(token, items)))
+        let mut array_expression = ArrayExpression::new(token, items);
+        array_expression.span.end = self.cur_token.span.end;
+        Some(Box::new(array_expression))
     }
 
-    // When this function is called, cur_token should point to the starting DoubleQuotation.
-    // When it returns, cur_token should point to the ending DoubleQuotation.
-    fn parse_string_expression(&mut self) -> Option<Box<dyn Expression>> {
-        let token = if self.cur_token.token_type == TokenType::DoubleQuotation {
+    fn parse_hash_expression(&mut self) -> Option<Box<dyn Expression>> {
+        // cur_token points to the LBrace here
+        let token = if self.cur_token.token_type == TokenType::LBrace {
             self.cur_token.clone()
         } else {
             return None;
         };
-        let mut value = String::new();
-        if !self.expect_peek(TokenType::DoubleQuotation) {
+        let mut pairs = Vec::new();
+        if !self.expect_peek(TokenType::RBrace) {
+            self.next_token();
+            loop {
+                let key = self.parse_expression(Precedence::Lowest as i32)?;
+                if !self.expect_peek(TokenType::Colon) {
+                    self.expect_error(TokenType::Colon);
+                    return None;
+                }
+                self.next_token();
+                let value = self.parse_expression(Precedence::Lowest as i32)?;
+                pairs.push((key, value));
+                // If the next token is RBrace, then break out of the loop
+                if self.expect_peek(TokenType::RBrace) {
+                    break;
+                }
+                // Otherwise, we expect a comma after the pair
+                if !self.expect_peek(TokenType::Comma) {
+                    self.expect_error(TokenType::Comma);
+                    return None;
+                }
+                self.next_token();
+            }
+        }
+        // cur_token points to the RBrace here
+        let mut hash_expression = HashExpression::new(token, pairs);
+        hash_expression.span.end = self.cur_token.span.end;
+        Some(Box::new(hash_expression))
+    }
+
+    // The lexer has already replaced escape sequences, so the literal of the token is the value
+    // of the string
+    fn parse_string_expression(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone();
+        let value = token.literal.clone();
+        Some(Box::new(StringExpression::new(token, value)))
+    }
+
+    // When this function is called, cur_token should point to the StringStart.
+    // When it returns, cur_token should point to the StringEnd.
+    fn parse_interpolated_string_expression(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone();
+        let mut strings = vec![token.literal.clone()];
+        let mut expressions = Vec::new();
+        loop {
             self.next_token();
-            value = self.cur_token.literal.clone();
-            if self.expect_peek(TokenType::DoubleQuotation) {
-                Some(Box::new(StringExpression::new(token, value)))
+            expressions.push(self.parse_expression(Precedence::Lowest as i32)?);
+            if self.expect_peek(TokenType::StringMiddle) {
+                strings.push(self.cur_token.literal.clone());
+            } else if self.expect_peek(TokenType::StringEnd) {
+                strings.push(self.cur_token.literal.clone());
+                break;
             } else {
-                self.expect_error(TokenType::DoubleQuotation);
-                None
+                self.expect_error(TokenType::StringEnd);
+                return None;
             }
-        } else {
-            Some(Box::new(StringExpression::new(token, value)))
         }
+        let mut interpolated_string =
+            InterpolatedStringExpression::new(token, strings, expressions);
+        interpolated_string.span.end = self.cur_token.span.end;
+        Some(Box::new(interpolated_string))
     }
 
     fn no_prefix_function_error(&mut self, token_type: TokenType) {
-        self.errors.push(format!(
-            "No prefix parse function found for {token_type:?} found"
+        self.errors.push(ParserError::new(
+            ParserErrorKind::NoPrefixParseFunction { found: token_type },
+            self.cur_token.span,
         ))
     }
 
     fn token_to_precedence(token_type: TokenType) -> Precedence {
         match token_type {
+            TokenType::Assign
+            | TokenType::PlusAssign
+            | TokenType::MinusAssign
+            | TokenType::StarAssign
+            | TokenType::SlashAssign
+            | TokenType::PercentAssign => Precedence::Assign,
+            TokenType::LogicalOr => Precedence::LogicalOr,
+            TokenType::LogicalAnd => Precedence::LogicalAnd,
             TokenType::Eq => Precedence::Equals,
             TokenType::NotEq => Precedence::Equals,
             TokenType::LArrow => Precedence::LessGreater,
@@ -578,6 +890,7 @@ impl Parser {
             TokenType::Minus => Precedence::Sum,
             TokenType::Star => Precedence::Product,
             TokenType::Slash => Precedence::Product,
+            TokenType::Percent => Precedence::Product,
             TokenType::LSquare => Precedence::Call,
             TokenType::LParen => Precedence::Call,
             _ => Precedence::Lowest,
@@ -598,9 +911,12 @@ impl Parser {
 
     /// Adds a parser error indicating the expected token type was not found
     fn expect_error(&mut self, expected_token_type: TokenType) {
-        self.errors.push(format!(
-            "Expected  {:?}, found {:?} instead",
-            expected_token_type, self.peek_token.token_type
+        self.errors.push(ParserError::new(
+            ParserErrorKind::UnexpectedToken {
+                expected: expected_token_type,
+                found: self.peek_token.token_type,
+            },
+            self.peek_token.span,
         ))
     }
 
@@ -616,9 +932,15 @@ impl Parser {
     fn next_token(&mut self) {
         self.cur_token = self.peek_token.clone();
         self.peek_token = self.lexer.next_token();
+        for error in self.lexer.take_errors() {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::Lexer(error.kind),
+                error.span,
+            ));
+        }
     }
 
-    fn get_errors(&self) -> &Vec<String> {
+    pub fn errors(&self) -> &[ParserError] {
         &self.errors
     }
 
@@ -638,21 +960,11 @@ impl Parser {
     }
 }
 
-pub fn has_parser_errors(parser: &Parser) -> bool {
-    let errors = parser.get_errors();
-    if !errors.is_empty() {
-        eprintln!("Parser has {} errors(s)", errors.len());
-        for error in errors {
-            eprintln!("Parser error: {error}");
-        }
-        true
-    } else {
-        false
-    }
-}
-
 enum Precedence {
     Lowest,
+    Assign,
+    LogicalOr,
+    LogicalAnd,
     Equals,
     LessGreater,
     Sum,
@@ -2,14 +2,15 @@
 use crate::ast::StringExpression;
 #[cfg(test)]
 use crate::ast::{
-    ArrayExpression, BooleanLiteral, CallExpression, ExpressionStatement, FunctionLiteral,
-    Identifier, IfExpression, IndexExpression, InfixExpression, IntegerLiteral, LetStatement, Node,
-    NullLiteral, PrefixExpression, ReturnStatement,
+    ArrayExpression, BooleanLiteral, CallExpression, ExpressionStatement, FloatLiteral,
+    ForStatement, FunctionLiteral, HashExpression, Identifier, IfExpression, IndexExpression,
+    InfixExpression, IntegerLiteral, InterpolatedStringExpression, LetStatement, Node, NullLiteral,
+    PrefixExpression, ReturnStatement, WhileStatement,
 };
 #[cfg(test)]
-use crate::lexer::Lexer;
+use crate::lexer::{Lexer, LexerErrorKind};
 #[cfg(test)]
-use crate::parser::{Parser, has_parser_errors};
+use crate::parser::{Parser, ParserErrorKind};
 #[cfg(test)]
 use crate::token::TokenType;
 
@@ -75,6 +76,63 @@ fn let_rec_statements() {
     }
 }
 
+#[test]
+fn let_rec_and_statements() {
+    let input = "
+        let rec is_even = fun(n) { is_odd(n) } and is_odd = fun(n) { is_even(n) };
+    ";
+
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    assert!(!has_parser_errors(&parser));
+    assert!(program.statements.len() == 1);
+    let let_statement = program.statements[0]
+        .as_any()
+        .downcast_ref::<LetStatement>()
+        .expect("Expected let statement");
+    assert!(let_statement.rec);
+    let bindings: Vec<_> = let_statement
+        .bindings()
+        .map(|(name, value)| (name.value.clone(), value.string()))
+        .collect();
+    assert_eq!(
+        bindings,
+        vec![
+            ("is_even".to_string(), "fun(n) { is_odd(n); }".to_string()),
+            ("is_odd".to_string(), "fun(n) { is_even(n); }".to_string()),
+        ]
+    );
+    assert_eq!(
+        let_statement.string(),
+        "let rec is_even = fun(n) { is_odd(n); } and is_odd = fun(n) { is_even(n); };"
+    );
+    assert_eq!(
+        &input[let_statement.span.start.offset..let_statement.span.end.offset],
+        input.trim()
+    );
+}
+
+#[test]
+fn var_statements() {
+    let input = "var x = 5; var y = x + 1;";
+
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    assert!(!has_parser_errors(&parser));
+    assert_eq!(program.statements.len(), 2);
+    let let_statement = program.statements[0]
+        .as_any()
+        .downcast_ref::<LetStatement>()
+        .expect("Expected let statement");
+    assert!(let_statement.mutable);
+    assert!(!let_statement.rec);
+    assert_eq!(let_statement.token.token_type, TokenType::Var);
+    assert_eq!(let_statement.name.value, "x");
+    assert_eq!(program.string(), "var x = 5;\nvar y = (x + 1);");
+}
+
 #[test]
 fn return_statements() {
     let input = "
@@ -87,13 +145,12 @@ fn return_statements() {
     let program = parser.parse_program();
     assert!(!has_parser_errors(&parser));
     assert!(program.statements.len() == 2);
-    for i in 0..program.statements.len() {
-        let statement = &program.statements[i];
+    for (statement, expected_value) in program.statements.iter().zip(expected_values) {
         let return_statement = statement
             .as_any()
             .downcast_ref::<ReturnStatement>()
             .expect("Expected return statement");
-        assert!(check_return_statement(return_statement, expected_values[i]))
+        assert!(check_return_statement(return_statement, expected_value))
     }
 }
 
@@ -283,6 +340,31 @@ fn integer_literal_expression() {
     assert_eq!(integer_literal.token_literal(), "10");
 }
 
+#[test]
+fn float_literal_expression() {
+    let tests = vec![("2.5;", 2.5), ("1e-9;", 1e-9), ("2.5E3;", 2500.0)];
+
+    for (input, expected_value) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser));
+        assert!(program.statements.len() == 1);
+
+        let expression_statement = program.statements[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatement>()
+            .expect("Expected expression statement");
+        let float_literal = expression_statement
+            .expression
+            .as_any()
+            .downcast_ref::<FloatLiteral>()
+            .expect("Expected float literal");
+        assert_eq!(float_literal.value, expected_value);
+        assert_eq!(float_literal.string(), input.trim_end_matches(';'));
+    }
+}
+
 #[test]
 fn boolean_literal_expression() {
     let input = "false;";
@@ -470,6 +552,18 @@ fn operator_precedence() {
         ("add(2 * 3, 4 + 5);", "add((2 * 3), (4 + 5))"),
         ("-add(2, 3);", "(-add(2, 3))"),
         ("!add(true, false);", "(!add(true, false))"),
+        ("5 % 3 * 2 + 1;", "(((5 % 3) * 2) + 1)"),
+        ("1 + 5 % 3;", "(1 + (5 % 3))"),
+        ("a || b && c;", "(a || (b && c))"),
+        ("a && b || c && d;", "((a && b) || (c && d))"),
+        ("a || b || c;", "((a || b) || c)"),
+        ("1 < 2 && 3 == 4;", "((1 < 2) && (3 == 4))"),
+        ("!a && b;", "((!a) && b)"),
+        ("x = 1 + 2;", "(x = (1 + 2))"),
+        ("x = y = z;", "(x = (y = z))"),
+        ("x += a || b;", "(x += (a || b))"),
+        ("a[i][j] *= 2;", "(a[i][j] *= 2)"),
+        ("h[\"k\"] = f(x)[0];", "(h[\"k\"] = f(x)[0])"),
     ];
 
     for (input, expected) in tests {
@@ -517,6 +611,33 @@ fn call_expression() {
     assert_eq!(call_expression.arguments[2].string(), "(4 + 5)");
 }
 
+#[test]
+fn call_expression_callees() {
+    let tests = vec![
+        ("make_adder(1)(2);", "make_adder(1)"),
+        ("handlers[0](event);", "handlers[0]"),
+        ("(if (c) { f } else { g })(x);", "if (c) { f; }else { g; }"),
+        ("fun(x) { x }(1);", "fun(x) { x; }"),
+    ];
+    for (input, expected_callee) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser));
+        let expression_statement = program.statements[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatement>()
+            .expect("Expected expression statement");
+        let call_expression = expression_statement
+            .expression
+            .as_any()
+            .downcast_ref::<CallExpression>()
+            .expect("Expected call expression");
+        assert_eq!(call_expression.function.string(), expected_callee);
+        assert_eq!(call_expression.arguments.len(), 1);
+    }
+}
+
 #[test]
 fn test_array_expressions() {
     let tests = vec![
@@ -558,6 +679,57 @@ fn test_array_expressions() {
     }
 }
 
+#[test]
+fn hash_expressions() {
+    let tests = vec![
+        ("{};", vec![]),
+        (
+            r#"{"one": 1, "two": 2};"#,
+            vec![(r#""one""#, "1"), (r#""two""#, "2")],
+        ),
+        (
+            r#"{1: x + 1, true: [1], "f": fun(x) { x }};"#,
+            vec![
+                ("1", "(x + 1)"),
+                ("true", "[1]"),
+                (r#""f""#, "fun(x) { x; }"),
+            ],
+        ),
+    ];
+
+    for (input, expected_pairs) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser));
+        assert_eq!(program.statements.len(), 1);
+        let expression_statement = program.statements[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatement>()
+            .expect("Expected expression statement");
+        let hash_expression = expression_statement
+            .expression
+            .as_any()
+            .downcast_ref::<HashExpression>()
+            .expect("Expected hash expression");
+
+        let pairs: Vec<_> = hash_expression
+            .pairs
+            .iter()
+            .map(|(key, value)| (key.string(), value.string()))
+            .collect();
+        let expected_pairs: Vec<_> = expected_pairs
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(pairs, expected_pairs);
+        assert_eq!(
+            &input[hash_expression.span.start.offset..hash_expression.span.end.offset],
+            input.trim_end_matches(';')
+        );
+    }
+}
+
 #[test]
 fn test_string_expression() {
     let tests = vec![
@@ -566,6 +738,7 @@ fn test_string_expression() {
             r#""123 random string !wowo! !@#12309""#,
             "123 random string !wowo! !@#12309",
         ),
+        (r#""tab\there \"quoted\"""#, "tab\there \"quoted\""),
     ];
 
     for (input, expected_value) in tests {
@@ -586,7 +759,50 @@ fn test_string_expression() {
             .downcast_ref::<StringExpression>()
             .expect("Expected string expression");
 
-        assert_eq!(string_expression.value, expected_value)
+        assert_eq!(string_expression.value, expected_value);
+        assert_eq!(string_expression.string(), input);
+    }
+}
+
+#[test]
+fn interpolated_string_expression() {
+    let tests = vec![
+        (r#""hello ${name}!""#, vec!["hello ", "!"], vec!["name"]),
+        (
+            r#""${a + b}${f(x, "${y}")}""#,
+            vec!["", "", ""],
+            vec!["(a + b)", r#"f(x, "${y}")"#],
+        ),
+        (r#""\${not} ${x}""#, vec!["${not} ", ""], vec!["x"]),
+    ];
+
+    for (input, expected_strings, expected_expressions) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser));
+        assert_eq!(program.statements.len(), 1);
+        let expression_statement = program.statements[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatement>()
+            .expect("Expected expression statement");
+        let interpolated_string = expression_statement
+            .expression
+            .as_any()
+            .downcast_ref::<InterpolatedStringExpression>()
+            .expect("Expected interpolated string expression");
+
+        assert_eq!(interpolated_string.strings, expected_strings);
+        let expressions: Vec<_> = interpolated_string
+            .expressions
+            .iter()
+            .map(|expression| expression.string())
+            .collect();
+        assert_eq!(expressions, expected_expressions);
+        assert_eq!(
+            &input[interpolated_string.span.start.offset..interpolated_string.span.end.offset],
+            input
+        );
     }
 }
 
@@ -643,6 +859,91 @@ fn index_expressions() {
     }
 }
 
+#[test]
+fn slice_expressions() {
+    let tests = vec![
+        ("arr[1:2];", "arr[1:2]"),
+        ("arr[:2];", "arr[:2]"),
+        ("arr[1:];", "arr[1:]"),
+        ("arr[:];", "arr[:]"),
+        ("s[i + 1:len(s) - 1];", "s[(i + 1):(len(s) - 1)]"),
+        ("{a[1:]: 1};", "{a[1:]: 1}"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser));
+        assert_eq!(program.statements.len(), 1);
+        let expression_statement = program.statements[0]
+            .as_any()
+            .downcast_ref::<ExpressionStatement>()
+            .expect("Expected expression statement");
+        assert_eq!(expression_statement.expression.string(), expected);
+        let expression = &expression_statement.expression;
+        assert_eq!(
+            &input[expression.span().start.offset..expression.span().end.offset],
+            input.trim_end_matches(';')
+        );
+    }
+}
+
+#[test]
+fn loop_statements() {
+    let tests = vec![
+        ("while (x < 10) { x; }", "while ((x < 10)) { x; }"),
+        ("while x { }", "while (x) {  }"),
+        (
+            "while true { if (x) { break; } continue; };",
+            "while (true) { if (x) { break; }; continue; }",
+        ),
+        ("for x in [1, 2] { x; }", "for x in [1, 2] { x; }"),
+        (
+            "for c in \"abc\" { for k in h { break; } }",
+            "for c in \"abc\" { for k in h { break; } }",
+        ),
+        ("for i in range(0, n) {}", "for i in range(0, n) {  }"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(!has_parser_errors(&parser), "Input: {input}");
+        assert_eq!(program.statements.len(), 1, "Input: {input}");
+        assert_eq!(program.statements[0].string(), expected);
+    }
+
+    // Statements after a loop are parsed as separate statements
+    let input = "while (x) { 1; } let y = 2; for i in a { i; } y";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    assert!(!has_parser_errors(&parser));
+    assert_eq!(program.statements.len(), 4);
+    let while_statement = program.statements[0]
+        .as_any()
+        .downcast_ref::<WhileStatement>()
+        .expect("Expected while statement");
+    assert_eq!(while_statement.condition.string(), "x");
+    let for_statement = program.statements[2]
+        .as_any()
+        .downcast_ref::<ForStatement>()
+        .expect("Expected for statement");
+    assert_eq!(for_statement.variable.value, "i");
+    assert_eq!(for_statement.collection.string(), "a");
+    assert_eq!(
+        &input[for_statement.span.start.offset..for_statement.span.end.offset],
+        "for i in a { i; }"
+    );
+}
+
+#[cfg(test)]
+fn has_parser_errors(parser: &Parser) -> bool {
+    !parser.errors().is_empty()
+}
+
 #[cfg(test)]
 fn check_let_statement(
     let_statement: &LetStatement,
@@ -657,7 +958,7 @@ fn check_let_statement(
 }
 
 #[cfg(test)]
-fn check_params_list(parameters: &Vec<Identifier>, expected: Vec<&str>) {
+fn check_params_list(parameters: &[Identifier], expected: Vec<&str>) {
     assert_eq!(parameters.len(), expected.len());
     for (i, param) in parameters.iter().enumerate() {
         assert_eq!(param.value, expected[i]);
@@ -672,3 +973,216 @@ fn check_return_statement(
     return_statement.token.token_type == TokenType::Return
         && return_statement.return_value.string() == expected_expression_literal
 }
+
+#[test]
+fn node_spans() {
+    let input = "let x = add(1, 2 * y);\nif (x > 1) { [x, 2][0] } else { \"no\" }\nreturn -x;";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    assert!(!has_parser_errors(&parser));
+    assert_eq!(program.statements.len(), 3);
+
+    let source_of = |node: &dyn Node| {
+        let span = node.span();
+        &input[span.start.offset..span.end.offset]
+    };
+
+    let let_statement = program.statements[0]
+        .as_any()
+        .downcast_ref::<LetStatement>()
+        .expect("Expected let statement");
+    assert_eq!(source_of(let_statement), "let x = add(1, 2 * y);");
+    assert_eq!(source_of(&let_statement.name), "x");
+    assert_eq!(source_of(let_statement.value.as_ref()), "add(1, 2 * y)");
+    let call_expression = let_statement
+        .value
+        .as_any()
+        .downcast_ref::<CallExpression>()
+        .expect("Expected call expression");
+    assert_eq!(source_of(call_expression.arguments[1].as_ref()), "2 * y");
+
+    let expression_statement = program.statements[1]
+        .as_any()
+        .downcast_ref::<ExpressionStatement>()
+        .expect("Expected expression statement");
+    assert_eq!(
+        source_of(expression_statement),
+        "if (x > 1) { [x, 2][0] } else { \"no\" }"
+    );
+    let if_expression = expression_statement
+        .expression
+        .as_any()
+        .downcast_ref::<IfExpression>()
+        .expect("Expected if expression");
+    assert_eq!(source_of(if_expression.consequences[0].0.as_ref()), "x > 1");
+    assert_eq!(source_of(&if_expression.consequences[0].1), "{ [x, 2][0] }");
+    assert_eq!(
+        source_of(if_expression.consequences[0].1.statements[0].as_ref()),
+        "[x, 2][0]"
+    );
+    assert_eq!(
+        source_of(if_expression.alternative.as_ref().unwrap()),
+        "{ \"no\" }"
+    );
+
+    let return_statement = &program.statements[2];
+    assert_eq!(source_of(return_statement.as_ref()), "return -x;");
+    assert_eq!(return_statement.span().start.line, 3);
+    assert_eq!(return_statement.span().start.column, 1);
+}
+
+#[test]
+fn structured_parser_errors() {
+    let tests = vec![
+        (
+            "let x 5;",
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::Assign,
+                found: TokenType::Int,
+            },
+            "P0001",
+            "5",
+        ),
+        (
+            "let x = );",
+            ParserErrorKind::NoPrefixParseFunction {
+                found: TokenType::RParen,
+            },
+            "P0002",
+            ")",
+        ),
+        (
+            "99999999999999999999;",
+            ParserErrorKind::InvalidIntegerLiteral {
+                literal: "99999999999999999999".to_string(),
+            },
+            "P0003",
+            "99999999999999999999",
+        ),
+        (
+            "let rec f = fun() { g() } and g = 5;",
+            ParserErrorKind::RecursiveBindingNotFunction {
+                name: "g".to_string(),
+            },
+            "P0006",
+            "5",
+        ),
+        (
+            "{1 2};",
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::Colon,
+                found: TokenType::Int,
+            },
+            "P0001",
+            "2",
+        ),
+        (
+            "1e999;",
+            ParserErrorKind::InvalidFloatLiteral {
+                literal: "1e999".to_string(),
+            },
+            "P0007",
+            "1e999",
+        ),
+        // Bindings made with let rec can't be mutable
+        (
+            "var rec f = fun() { f() };",
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::Ident,
+                found: TokenType::Rec,
+            },
+            "P0001",
+            "rec",
+        ),
+        // Only let rec statements can have several bindings
+        (
+            "let f = 1 and g = 2;",
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::Semicolon,
+                found: TokenType::And,
+            },
+            "P0001",
+            "and",
+        ),
+        (
+            "for x [1] {}",
+            ParserErrorKind::UnexpectedToken {
+                expected: TokenType::In,
+                found: TokenType::LSquare,
+            },
+            "P0001",
+            "[",
+        ),
+        (
+            "if (x) { break; }",
+            ParserErrorKind::LoopControlOutsideLoop {
+                keyword: "break".to_string(),
+            },
+            "P0008",
+            "break",
+        ),
+        // Loops outside of a function can't be stopped from inside it
+        (
+            "while (x) { let f = fun() { continue; }; }",
+            ParserErrorKind::LoopControlOutsideLoop {
+                keyword: "continue".to_string(),
+            },
+            "P0008",
+            "continue",
+        ),
+        (
+            "f(x) = 1;",
+            ParserErrorKind::InvalidAssignmentTarget {
+                operator: "=".to_string(),
+            },
+            "P0009",
+            "f(x)",
+        ),
+        (
+            "a[1:] += [2];",
+            ParserErrorKind::InvalidAssignmentTarget {
+                operator: "+=".to_string(),
+            },
+            "P0009",
+            "a[1:]",
+        ),
+        (
+            "x + y = 1;",
+            ParserErrorKind::InvalidAssignmentTarget {
+                operator: "=".to_string(),
+            },
+            "P0009",
+            "x + y",
+        ),
+        // Errors found by the lexer are reported by the parser
+        (
+            "let s = \"abc;",
+            ParserErrorKind::Lexer(LexerErrorKind::UnterminatedString),
+            "L0001",
+            "\"abc;",
+        ),
+        (
+            r#"let s = "a\b";"#,
+            ParserErrorKind::Lexer(LexerErrorKind::InvalidEscapeSequence {
+                sequence: r"\b".to_string(),
+            }),
+            "L0002",
+            r"\b",
+        ),
+    ];
+
+    for (input, expected_kind, expected_code, expected_source) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+        let error = &parser.errors()[0];
+        assert_eq!(error.kind, expected_kind, "Input: {input}");
+        assert_eq!(error.code(), expected_code, "Input: {input}");
+        assert_eq!(
+            &input[error.span.start.offset..error.span.end.offset],
+            expected_source,
+            "Input: {input}"
+        );
+    }
+}
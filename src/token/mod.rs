@@ -4,9 +4,17 @@ pub enum TokenType {
     Eof,
     Ident,
     Int,
+    Float,
     Assign,
+    // Compound assignment operators, like `+=`
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
     Plus,
     Comma,
+    Colon,
     Semicolon,
     LParen,
     RParen,
@@ -14,16 +22,30 @@ pub enum TokenType {
     RBrace,
     LSquare,
     RSquare,
-    DoubleQuotation,
     StringValue,
+    // A string literal with interpolations is split into a StringStart token for the text before
+    // the first `${`, a StringMiddle token for the text between each `}` and the next `${`, and a
+    // StringEnd token for the text after the last `}`. The tokens of the interpolated expressions
+    // come in between.
+    StringStart,
+    StringMiddle,
+    StringEnd,
     Function,
     Let,
+    // Like Let, but the binding can be assigned to
+    Var,
     // Used in let rec statements
     Rec,
+    // Separates the bindings of a let rec statement
+    And,
     Bang,
     Minus,
     Slash,
     Star,
+    Percent,
+    // `&&` and `||`, as opposed to the `and` keyword
+    LogicalAnd,
+    LogicalOr,
     LArrow,
     RArrow,
     True,
@@ -31,6 +53,12 @@ pub enum TokenType {
     If,
     Else,
     Return,
+    While,
+    For,
+    // Separates the loop variable from the collection in a for loop
+    In,
+    Break,
+    Continue,
     Eq,
     NotEq,
     GreaterEq,
@@ -38,10 +66,49 @@ pub enum TokenType {
     Null,
 }
 
+/// A location in the source text. `line` and `column` are 1-based and count characters, while
+/// `offset` is the 0-based byte offset into the source.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize, offset: usize) -> Self {
+        Self {
+            line,
+            column,
+            offset,
+        }
+    }
+}
+
+/// A range of source text. `start` points to the first character in the range and `end` points
+/// one past the last character.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns a span that starts where `self` starts and ends where `other` ends.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
+    pub span: Span,
 }
 
 impl Token {
@@ -49,6 +116,15 @@ impl Token {
         Self {
             token_type,
             literal: literal.to_string(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn with_span(token_type: TokenType, literal: &str, span: Span) -> Self {
+        Self {
+            token_type,
+            literal: literal.to_string(),
+            span,
         }
     }
 }